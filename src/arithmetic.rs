@@ -6,7 +6,9 @@ pub fn logic_left_with_carry(value: u32, shift: u32) -> (u32, bool) {
     if shift == 0 {
         (value, false)
     } else {
-        (value << shift, value << (shift - 1) >> 31 & 1 == 1)
+        //移位量可能不小于32(来自寄存器), 扩展到64位后再移
+        let extended = (value as u64).checked_shl(shift).unwrap_or(0);
+        (extended as u32, extended >> 32 & 1 == 1)
     }
 }
 
@@ -20,7 +22,10 @@ pub fn logic_right_with_carry(value: u32, shift: u32) -> (u32, bool) {
     if shift == 0 {
         (value, false)
     } else {
-        (value >> shift, value >> (shift - 1) & 1 == 1)
+        (
+            value.checked_shr(shift).unwrap_or(0),
+            value.checked_shr(shift - 1).unwrap_or(0) & 1 == 1,
+        )
     }
 }
 
@@ -34,9 +39,10 @@ pub fn arith_right_with_carry(value: u32, shift: u32) -> (u32, bool) {
     if shift == 0 {
         (value, false)
     } else {
+        //移位量不小于32时结果全是符号位
         (
-            ((value as i32) >> shift) as u32,
-            value >> (shift - 1) & 1 == 1,
+            ((value as i32) >> shift.min(31)) as u32,
+            ((value as i32) >> (shift - 1).min(31)) & 1 == 1,
         )
     }
 }
@@ -51,8 +57,7 @@ pub fn rotate_right_with_carry(value: u32, shift: u32) -> (u32, bool) {
     if shift == 0 {
         (value, false)
     } else {
-        let shift = shift % 32;
-        let result = (value >> shift) | (value << (32 - shift));
+        let result = value.rotate_right(shift % 32);
         let carry_out = result >> 31 & 1 == 1;
         (result, carry_out)
     }
@@ -76,6 +81,8 @@ pub fn rotate_right_extend(value: u32, carry_in: bool) -> u32 {
 //P290
 pub fn shift_c(value: u32, shift_style: ShiftStyle, amount: u32, carry_in: bool) -> (u32, bool) {
     match shift_style {
+        //移位量为0时carry保持不变
+        ShiftStyle::LSL | ShiftStyle::LSR | ShiftStyle::ASR if amount == 0 => (value, carry_in),
         ShiftStyle::LSL => logic_left_with_carry(value, amount),
         ShiftStyle::LSR => logic_right_with_carry(value, amount),
         ShiftStyle::ASR => arith_right_with_carry(value, amount),
@@ -89,6 +96,43 @@ pub fn shift_c(value: u32, shift_style: ShiftStyle, amount: u32, carry_in: bool)
     }
 }
 
+//P200
+//Arm指令中的修饰立即数: 8位立即数循环右移2*rotation位
+//不能用shift_c, 那里ROR移0位表示RRX, 而这里不循环移位时carry保持不变
+pub fn arm_expand_imm_c(imm12: u32, carry_in: bool) -> (u32, bool) {
+    let unrotated_value = imm12 & 0xff;
+    let amount = 2 * (imm12 >> 8 & 0xf);
+    if amount == 0 {
+        (unrotated_value, carry_in)
+    } else {
+        rotate_right_with_carry(unrotated_value, amount)
+    }
+}
+
+//P200
+pub fn arm_expand_imm(imm12: u32) -> u32 {
+    //carry_in不影响结果
+    arm_expand_imm_c(imm12, false).0
+}
+
+//P233
+//Thumb指令中的修饰立即数
+pub fn thumb_expand_imm_c(imm12: u32, carry_in: bool) -> (u32, bool) {
+    let imm8 = imm12 & 0xff;
+    if imm12 >> 10 & 0b11 == 0b00 {
+        let imm32 = match imm12 >> 8 & 0b11 {
+            0b00 => imm8,
+            0b01 => imm8 << 16 | imm8,
+            0b10 => imm8 << 24 | imm8 << 8,
+            _ => imm8 << 24 | imm8 << 16 | imm8 << 8 | imm8,
+        };
+        (imm32, carry_in)
+    } else {
+        let unrotated_value = 1 << 7 | imm12 & 0x7f;
+        rotate_right_with_carry(unrotated_value, imm12 >> 7 & 0x1f)
+    }
+}
+
 //P290
 pub fn shift(value: u32, shift_style: ShiftStyle, amount: u32, carry_in: bool) -> u32 {
     shift_c(value, shift_style, amount, carry_in).0
@@ -96,12 +140,12 @@ pub fn shift(value: u32, shift_style: ShiftStyle, amount: u32, carry_in: bool) -
 
 //P43
 pub fn add_with_carry(x: u32, y: u32, carry_in: bool) -> (u32, bool, bool) {
-    let unsigned_sum = x as u64 + y as u64 + (carry_in as u64);
-    let signed_num = (x as i32 as i64) + (y as i32 as i64) + (carry_in as i64);
-    let result = unsigned_sum & 0xffffffff;
-    let carry_out = result != unsigned_sum;
-    let overflow = (result as i64) != signed_num;
-    (result as u32, carry_out, overflow)
+    //保留完整的32位结果, 两次相加中任意一次进位就是carry_out
+    let (partial, carry1) = x.overflowing_add(y);
+    let (result, carry2) = partial.overflowing_add(carry_in as u32);
+    //两个加数符号相同而结果的符号不同时有符号溢出
+    let overflow = ((x ^ result) & (y ^ result)) >> 31 == 1;
+    (result, carry1 || carry2, overflow)
 }
 
 //P2368
@@ -115,28 +159,29 @@ pub fn bit_count(x: u32) -> u32 {
 
 //P44
 pub fn signed_sat_q(i: i64, n: u32) -> (u32, bool) {
-    let max = ((1 << (n - 1)) - 1) as i64;
-    let min = -(1 << (n - 1)) as i64;
+    //n可以是32, 在i64中移位避免溢出
+    let max = (1i64 << (n - 1)) - 1;
+    let min = -(1i64 << (n - 1));
     let (result, saturated) = if i > max {
         (max, true)
     } else if i < min {
         (min, true)
     } else {
-        (i, true)
+        (i, false)
     };
     (result as u32, saturated)
 }
 
 //P44
 pub fn unsigned_sat_q(i: i64, n: u32) -> (u32, bool) {
-    let max = ((1 << n) - 1) as i64;
-    let min = 0 as i64;
+    let max = (1i64 << n) - 1;
+    let min = 0;
     let (result, saturated) = if i > max {
         (max, true)
     } else if i < min {
         (min, true)
     } else {
-        (i, true)
+        (i, false)
     };
     (result as u32, saturated)
 }
@@ -168,3 +213,132 @@ pub fn sat(i: i64, n: u32, unsigned: bool) -> u32 {
         signed_sat(i, n)
     }
 }
+
+//16位SIMD饱和运算每个通道的饱和, 结果是这个通道的16位
+pub fn sat16_signed(i: i32) -> i16 {
+    i.clamp(i16::MIN as i32, i16::MAX as i32) as i16
+}
+
+pub fn sat16_unsigned(i: i32) -> u16 {
+    i.clamp(0, u16::MAX as i32) as u16
+}
+
+//8位SIMD饱和运算每个通道的饱和, 结果是这个通道的8位
+pub fn sat8_signed(i: i32) -> i8 {
+    i.clamp(i8::MIN as i32, i8::MAX as i32) as i8
+}
+
+pub fn sat8_unsigned(i: i32) -> u8 {
+    i.clamp(0, u8::MAX as i32) as u8
+}
+
+//SMLAD, SMUAD这类双16位乘法的两个乘积(低半字之积, 高半字之积), 半字都按有符号数计算
+//m_swap为真时先交换m的两个半字
+pub fn dual_products(n: u32, m: u32, m_swap: bool) -> (i64, i64) {
+    let operand2 = if m_swap { rotate_right(m, 16) } else { m };
+    let product1 = n as i16 as i64 * operand2 as i16 as i64;
+    let product2 = (n >> 16) as i16 as i64 * (operand2 >> 16) as i16 as i64;
+    (product1, product2)
+}
+
+//截断成32位后是否丢失了有效位, 即result != SInt(result<31:0>), 这时要设置Q
+pub fn overflows_i32(result: i64) -> bool {
+    result != result as i32 as i64
+}
+
+//并行运算的两个16位通道(低半字, 高半字), signed为真时按有符号数扩展
+pub fn halfword_lanes(x: u32, signed: bool) -> [i32; 2] {
+    if signed {
+        [x as i16 as i32, (x >> 16) as i16 as i32]
+    } else {
+        [(x & 0xffff) as i32, (x >> 16) as i32]
+    }
+}
+
+//并行运算的四个8位通道, 从低字节开始
+pub fn byte_lanes(x: u32, signed: bool) -> [i32; 4] {
+    x.to_le_bytes().map(|byte| {
+        if signed {
+            byte as i8 as i32
+        } else {
+            byte as i32
+        }
+    })
+}
+
+//每个通道只保留低16位或者低8位
+pub fn pack_halfwords(lanes: [i32; 2]) -> u32 {
+    (lanes[1] as u32 & 0xffff) << 16 | (lanes[0] as u32 & 0xffff)
+}
+
+pub fn pack_bytes(lanes: [i32; 4]) -> u32 {
+    u32::from_le_bytes(lanes.map(|lane| lane as u8))
+}
+
+//QADD16, UQSUB8这类饱和并行运算: 每个通道单独饱和到16位或者8位再拼起来, 不影响GE和Q
+pub fn saturate_halfwords(lanes: [i32; 2], signed: bool) -> u32 {
+    pack_halfwords(lanes.map(|lane| {
+        if signed {
+            sat16_signed(lane) as i32
+        } else {
+            sat16_unsigned(lane) as i32
+        }
+    }))
+}
+
+pub fn saturate_bytes(lanes: [i32; 4], signed: bool) -> u32 {
+    pack_bytes(lanes.map(|lane| {
+        if signed {
+            sat8_signed(lane) as i32
+        } else {
+            sat8_unsigned(lane) as i32
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn add_with_carry_flags() {
+        assert_eq!(add_with_carry(1, 2, false), (3, false, false));
+        assert_eq!(add_with_carry(u32::MAX, 0, true), (0, true, false));
+        assert_eq!(
+            add_with_carry(0x7fffffff, 1, false),
+            (0x80000000, false, true)
+        );
+        //x - y = x + !y + 1
+        assert_eq!(add_with_carry(5, !5, true), (0, true, false));
+    }
+
+    #[test]
+    fn shifts_by_register_amount() {
+        assert_eq!(logic_left_with_carry(1, 32), (0, true));
+        assert_eq!(logic_left_with_carry(1, 33), (0, false));
+        assert_eq!(logic_right_with_carry(0x80000000, 32), (0, true));
+        assert_eq!(arith_right_with_carry(0x80000000, 40), (u32::MAX, true));
+        assert_eq!(rotate_right_with_carry(0x1, 1), (0x80000000, true));
+    }
+
+    #[test]
+    fn expand_immediates() {
+        assert_eq!(arm_expand_imm_c(0x0ff, true), (0xff, true));
+        assert_eq!(arm_expand_imm_c(0x102, false), (0x80000000, true));
+        assert_eq!(thumb_expand_imm_c(0x1ab, false), (0x00ab00ab, false));
+        assert_eq!(thumb_expand_imm_c(0x3ab, false), (0xabababab, false));
+        assert_eq!(thumb_expand_imm_c(0x47f, false), (0xff000000, true));
+    }
+
+    #[test]
+    fn saturation() {
+        assert_eq!(signed_sat_q(0x8000, 16), (0x7fff, true));
+        assert_eq!(signed_sat_q(-0x8001, 16), (0xffff8000, true));
+        assert_eq!(unsigned_sat_q(-1, 8), (0, true));
+        assert_eq!(unsigned_sat_q(0xff, 8), (0xff, false));
+        assert_eq!(saturate_halfwords([0x8000, -0x8001], true), 0x80007fff);
+        assert_eq!(saturate_halfwords([0x10000, -1], false), 0x0000ffff);
+        assert_eq!(saturate_bytes([0x80, -0x81, 1, -1], true), 0xff01807f);
+        assert_eq!(saturate_bytes([0x100, -1, 1, 0xff], false), 0xff0100ff);
+    }
+}
@@ -0,0 +1,40 @@
+use crate::{machine::Machine, vmerror::VMError};
+
+//断点表的大小, 单片机内存有限, 只保留少量断点
+pub const MAX_BREAKPOINTS: usize = 8;
+
+impl Machine {
+    pub fn set_breakpoint(&mut self, address: u32) -> Result<(), VMError> {
+        if self.breakpoints.contains(&Some(address)) {
+            return Ok(());
+        }
+        let slot = self
+            .breakpoints
+            .iter_mut()
+            .find(|slot| slot.is_none())
+            .ok_or(VMError::BreakpointTableFull)?;
+        *slot = Some(address);
+        Ok(())
+    }
+
+    pub fn clear_breakpoint(&mut self, address: u32) {
+        for slot in self.breakpoints.iter_mut() {
+            if *slot == Some(address) {
+                *slot = None;
+            }
+        }
+    }
+
+    //step在取指令前调用, 命中断点时返回true
+    //从断点继续运行时, 同一个地址不会再次命中
+    pub fn check_breakpoint(&mut self, address: u32) -> bool {
+        if self.resume_from_breakpoint.take() == Some(address) {
+            return false;
+        }
+        if self.breakpoints.contains(&Some(address)) {
+            self.resume_from_breakpoint = Some(address);
+            return true;
+        }
+        false
+    }
+}
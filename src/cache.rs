@@ -0,0 +1,189 @@
+use crate::{
+    machine::Machine,
+    protocol::{Command, receive_data},
+    serial::Serial,
+    vmerror::VMError,
+};
+
+//外部内存的写回缓存, 减少串口通信的次数
+pub const CACHE_LINE_SIZE: usize = 32;
+pub const CACHE_LINES: usize = 4;
+
+#[derive(Clone, Copy)]
+pub struct CacheLine {
+    //这一行在外部内存中的起始偏移
+    pub tag: u32,
+    pub data: [u8; CACHE_LINE_SIZE],
+    pub valid: bool,
+    pub dirty: bool,
+    //最近一次使用的时间, 用于选出要替换的行
+    pub last_used: u32,
+}
+
+impl Default for CacheLine {
+    fn default() -> Self {
+        CacheLine {
+            tag: 0,
+            data: [0; CACHE_LINE_SIZE],
+            valid: false,
+            dirty: false,
+            last_used: 0,
+        }
+    }
+}
+
+impl CacheLine {
+    pub fn write_back(&mut self, serial: &mut dyn Serial) -> Result<(), VMError> {
+        if self.valid && self.dirty {
+            Command::WriteBlock(self.tag, self.data.to_vec()).send(serial)?;
+            self.dirty = false;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct ExternalCache {
+    pub lines: [CacheLine; CACHE_LINES],
+    pub clock: u32,
+    //命中和未命中的次数, 方便调整缓存大小
+    pub hits: u32,
+    pub misses: u32,
+}
+
+impl ExternalCache {
+    //offset是相对外部内存起始的偏移, 返回对应的行, 不在缓存中时从电脑读取
+    fn line(&mut self, serial: &mut dyn Serial, offset: u32) -> Result<&mut CacheLine, VMError> {
+        let tag = offset & !(CACHE_LINE_SIZE as u32 - 1);
+        self.clock = self.clock.wrapping_add(1);
+        let index = match self
+            .lines
+            .iter()
+            .position(|line| line.valid && line.tag == tag)
+        {
+            Some(index) => {
+                self.hits = self.hits.wrapping_add(1);
+                index
+            }
+            None => {
+                self.misses = self.misses.wrapping_add(1);
+                //优先用空行, 否则替换最久没用的行
+                let index = (0..CACHE_LINES)
+                    .min_by_key(|&i| (self.lines[i].valid, self.lines[i].last_used))
+                    .unwrap();
+                let line = &mut self.lines[index];
+                line.write_back(serial)?;
+                line.valid = false;
+                Command::ReadBlock(tag, CACHE_LINE_SIZE as u32).send(serial)?;
+                let data = receive_data(serial)?;
+                if data.len() != CACHE_LINE_SIZE {
+                    return Err(VMError::BusError);
+                }
+                line.data.copy_from_slice(&data);
+                line.tag = tag;
+                line.valid = true;
+                index
+            }
+        };
+        let line = &mut self.lines[index];
+        line.last_used = self.clock;
+        Ok(line)
+    }
+
+    pub fn read(&mut self, serial: &mut dyn Serial, offset: u32) -> Result<u8, VMError> {
+        let line = self.line(serial, offset)?;
+        Ok(line.data[offset as usize % CACHE_LINE_SIZE])
+    }
+
+    pub fn write(
+        &mut self,
+        serial: &mut dyn Serial,
+        offset: u32,
+        value: u8,
+    ) -> Result<(), VMError> {
+        let line = self.line(serial, offset)?;
+        line.data[offset as usize % CACHE_LINE_SIZE] = value;
+        line.dirty = true;
+        Ok(())
+    }
+
+    //把所有脏行写回电脑, 行仍然保留在缓存中
+    pub fn flush(&mut self, serial: &mut dyn Serial) -> Result<(), VMError> {
+        for line in self.lines.iter_mut() {
+            line.write_back(serial)?;
+        }
+        Ok(())
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.lines.iter().any(|line| line.valid && line.dirty)
+    }
+}
+
+impl Machine {
+    //默认不开启, 开启后访问外部内存都经过缓存
+    pub fn enable_external_cache(&mut self) {
+        *self.external_cache.get_mut() = Some(ExternalCache::default());
+    }
+
+    //关闭前先写回脏行
+    pub fn disable_external_cache(&mut self) -> Result<(), VMError> {
+        self.flush_external_cache()?;
+        *self.external_cache.get_mut() = None;
+        Ok(())
+    }
+
+    //DSB时调用, 保证电脑能看到客户程序写入的数据
+    pub fn flush_external_cache(&self) -> Result<(), VMError> {
+        let mut cache = self.external_cache.borrow_mut();
+        let Some(cache) = cache.as_mut().filter(|cache| cache.is_dirty()) else {
+            return Ok(());
+        };
+        let mut serial = self.serial.borrow_mut();
+        let serial = serial.as_mut().ok_or(VMError::BusError)?.as_mut();
+        cache.flush(serial)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::boxed::Box;
+
+    use super::*;
+    use crate::machine::{HaltReason, tests::arm_machine};
+    use crate::memory::INTERNAL_SIZE;
+    use crate::serial::MockSerial;
+
+    fn frame(command: Command) -> (u8, Vec<u8>) {
+        (command.head(), command.data())
+    }
+
+    #[test]
+    fn exit_flushes_cached_write() {
+        let mut machine = arm_machine(&[
+            0xe3a02101, //mov r2, #0x40000000
+            0xe5801000, //str r1, [r0]
+            0xe5823000, //str r3, [r2]
+        ]);
+        let serial = MockSerial::new();
+        machine.attach_serial(Box::new(serial.clone()));
+        machine.enable_external_cache();
+        machine.cpu.regs[0] = INTERNAL_SIZE as u32 + 0x44;
+        machine.cpu.regs[1] = 0x44332211;
+        serial.push_reply(&[0; CACHE_LINE_SIZE]);
+        machine.step().unwrap();
+        machine.step().unwrap();
+        //写到缓存里, 还没有写回
+        assert_eq!(
+            serial.take_frames(),
+            [frame(Command::ReadBlock(0x40, CACHE_LINE_SIZE as u32))]
+        );
+        assert!(matches!(machine.run_bounded(10), HaltReason::GuestExit(0)));
+        let mut line = [0; CACHE_LINE_SIZE];
+        line[4..8].copy_from_slice(&[0x11, 0x22, 0x33, 0x44]);
+        assert_eq!(
+            serial.take_frames(),
+            [frame(Command::WriteBlock(0x40, line.to_vec()))]
+        );
+    }
+}
@@ -0,0 +1,39 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::{machine::Machine, memory::INTERNAL_SIZE};
+
+//内部内存每个半字1位, 100KiB内存需要6400字节, 所以默认不开启
+pub const COVERAGE_SIZE: usize = INTERNAL_SIZE / 2 / 8;
+
+impl Machine {
+    //清空之前的记录, 从现在开始记录执行过的指令
+    pub fn enable_coverage(&mut self) {
+        self.coverage = Some(vec![0; COVERAGE_SIZE]);
+    }
+
+    pub fn disable_coverage(&mut self) -> Option<Vec<u8>> {
+        self.coverage.take()
+    }
+
+    //由step在解码后调用, 只记录指令开头的半字, 外部内存中的指令不记录
+    pub fn record_coverage(&mut self) {
+        let address = self.mark as usize;
+        if let Some(coverage) = &mut self.coverage
+            && address < INTERNAL_SIZE
+        {
+            let index = address / 2;
+            coverage[index / 8] |= 1 << (index % 8);
+        }
+    }
+
+    pub fn is_covered(&self, address: u32) -> bool {
+        let index = address as usize / 2;
+        match &self.coverage {
+            Some(coverage) if (address as usize) < INTERNAL_SIZE => {
+                coverage[index / 8] >> (index % 8) & 1 == 1
+            }
+            _ => false,
+        }
+    }
+}
@@ -201,12 +201,63 @@ impl ITRegisterMut<'_> {
     }
 }
 
+//调试器保存和恢复的CPU状态, 可以在IT块中间停下后原样继续
+//it_state和cpsr中的IT位相同, 单独列出方便查看, 恢复时以it_state为准
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CpuState {
+    pub regs: [u32; 16],
+    pub cpsr: u32,
+    pub spsrs: [u32; 16],
+    pub it_state: u8,
+}
+
 impl Machine {
+    pub fn get_it_state(&self) -> u8 {
+        self.cpu.it_state().0
+    }
+
+    pub fn set_it_state(&mut self, it: u8) {
+        self.cpu.it_state_mut().set_value(it);
+    }
+
+    pub fn save_cpu_state(&self) -> CpuState {
+        CpuState {
+            regs: self.cpu.regs,
+            cpsr: self.cpu.cpsr.0,
+            spsrs: self.cpu.spsrs.map(|spsr| spsr.0),
+            it_state: self.get_it_state(),
+        }
+    }
+
+    pub fn restore_cpu_state(&mut self, state: &CpuState) {
+        self.cpu.regs = state.regs;
+        self.cpu.cpsr.0 = state.cpsr;
+        self.cpu.spsrs = state.spsrs.map(CPSRegister);
+        self.set_it_state(state.it_state);
+        self.mark = self.cpu.regs[PC_INDEX];
+    }
+
     //P52
     pub fn in_it_block(&self) -> bool {
         self.cpu.it_state().0 & 0b1111 != 0b0000
     }
 
+    //P52
+    pub fn it_advance(&mut self) {
+        let it = self.cpu.it_state().0;
+        let it = if it & 0b111 == 0b000 {
+            0
+        } else {
+            it & 0b1110_0000 | it << 1 & 0b1_1111
+        };
+        self.cpu.it_state_mut().set_value(it);
+    }
+
+    //P1150
+    pub fn current_mode_is_not_user(&self) -> bool {
+        self.cpu.cpsr.m() != 0b10000
+    }
+
     //P51
     pub fn current_instr_set(&self) -> InstrSet {
         match self.cpu.iset_state().0 {
@@ -229,3 +280,23 @@ impl Machine {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::machine::tests::thumb_machine;
+
+    #[test]
+    fn it_state_round_trip() {
+        let mut machine = thumb_machine(&[]);
+        assert!(!machine.in_it_block());
+        machine.set_it_state(0xab);
+        assert_eq!(machine.get_it_state(), 0xab);
+        //IT[1:0]在CPSR[26:25], IT[7:2]在CPSR[15:10]
+        assert_eq!(machine.cpu.cpsr.0 & 0x0600fc00, 0x0600a800);
+        assert!(machine.in_it_block());
+        //只剩最后一条指令时前进后就离开IT块
+        machine.set_it_state(0x08);
+        machine.it_advance();
+        assert!(!machine.in_it_block());
+    }
+}
@@ -0,0 +1,138 @@
+use alloc::format;
+use alloc::string::String;
+use yaxpeax_arch::{Decoder, LengthedInstruction, ReadError, Reader};
+use yaxpeax_arm::armv7::{Opcode, Operand};
+
+use crate::{machine::Machine, vmerror::VMError};
+
+//从address开始读内存, 不移动PC, 给反汇编用
+struct MemoryCursor<'a> {
+    machine: &'a Machine,
+    start: u32,
+    address: u32,
+}
+
+impl Reader<u32, u8> for MemoryCursor<'_> {
+    fn next(&mut self) -> Result<u8, ReadError> {
+        let byte = self.machine.read_memory(self.address)?;
+        self.address = self.address.wrapping_add(1);
+        Ok(byte)
+    }
+
+    fn next_n(&mut self, buf: &mut [u8]) -> Result<(), ReadError> {
+        for byte in buf.iter_mut() {
+            *byte = self.next()?;
+        }
+        Ok(())
+    }
+
+    fn mark(&mut self) {
+        self.start = self.address;
+    }
+
+    fn offset(&mut self) -> u32 {
+        self.address.wrapping_sub(self.start)
+    }
+
+    fn total_offset(&mut self) -> u32 {
+        self.address
+    }
+}
+
+impl Machine {
+    //反汇编address处的一条指令, 返回(带地址的指令文本, 字节数), 不改变机器的状态
+    //thumb指定按哪个指令集解码, 其余(版本, IT块, APSR.C)按当前状态
+    pub fn disassemble_at(&self, address: u32, thumb: bool) -> Result<(String, u32), VMError> {
+        let mut decoder = self.decoder();
+        decoder.set_thumb_mode(thumb);
+        let mut cursor = MemoryCursor {
+            machine: self,
+            start: address,
+            address,
+        };
+        let instruction = decoder.decode(&mut cursor).map_err(VMError::DecodeError)?;
+        let len = instruction.len().to_const();
+        //地址和跳转目标都用符号标注, 如"0x00000004 <main+0x4>: b $-0x4 ; 0x00000000 <main>"
+        let mut text = format!("{}: {}", self.annotate(address), instruction);
+        let operand = match instruction.opcode {
+            Opcode::B | Opcode::BL | Opcode::BLX => instruction.operands[0],
+            Opcode::CBZ | Opcode::CBNZ => instruction.operands[1],
+            _ => Operand::Nothing,
+        };
+        if let Operand::BranchOffset(..) | Operand::BranchThumbOffset(..) = operand {
+            let mut target = self.branch_target_from(address.wrapping_add(len), operand)?;
+            //跳到Arm时按4字节对齐, 同executor.rs中的BL, BLX
+            if matches!(instruction.opcode, Opcode::BL | Opcode::BLX)
+                && self.read(instruction.operands[4])? == 0b00
+            {
+                target = self.align(target, 4);
+            }
+            text.push_str(&format!(" ; {}", self.annotate(target)));
+        }
+        Ok((text, len))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+
+    use crate::machine::tests::arm_machine;
+
+    #[test]
+    fn disassemble_at_gives_text_and_length() {
+        let mut machine = arm_machine(&[
+            0xe3a00001, //mov r0, #1
+        ]);
+        machine.memory.data[0x100..0x106].copy_from_slice(&[
+            0x01, 0x20, //movs r0, #1
+            0x4f, 0xf0, 0x02, 0x01, //mov.w r1, #2
+        ]);
+        let (text, len) = machine.disassemble_at(0, false).unwrap();
+        assert!(text.starts_with("0x00000000: mov "), "{}", text);
+        assert_eq!(len, 4);
+        let (text, len) = machine.disassemble_at(0x100, true).unwrap();
+        assert!(text.starts_with("0x00000100: movs "), "{}", text);
+        assert_eq!(len, 2);
+        let (text, len) = machine.disassemble_at(0x102, true).unwrap();
+        assert!(text.starts_with("0x00000102: mov.w "), "{}", text);
+        assert_eq!(len, 4);
+        //不改变机器的状态
+        assert_eq!(machine.cpu.regs[15], 0);
+    }
+
+    #[test]
+    fn disassemble_at_annotates_address_and_branch_target() {
+        let mut machine = arm_machine(&[
+            0xe3a00001, //main: mov r0, #1
+            0xeafffffd, //b main
+            0xeb000000, //bl helper
+            0xe1a00000, //nop
+            0xe12fff1e, //helper: bx lr
+        ]);
+        machine.memory.data[0x104..0x106].copy_from_slice(&[
+            0xfc, 0xe7, //b.n 0x100
+        ]);
+        machine.add_symbol(0, String::from("main"));
+        machine.add_symbol(0x10, String::from("helper"));
+        machine.add_symbol(0x100, String::from("thumb_main"));
+
+        let (text, _) = machine.disassemble_at(0, false).unwrap();
+        assert!(text.starts_with("0x00000000 <main>: mov"), "{}", text);
+        let (text, _) = machine.disassemble_at(4, false).unwrap();
+        assert!(text.starts_with("0x00000004 <main+0x4>: b"), "{}", text);
+        assert!(text.ends_with(" ; 0x00000000 <main>"), "{}", text);
+        let (text, _) = machine.disassemble_at(8, false).unwrap();
+        assert!(text.ends_with(" ; 0x00000010 <helper>"), "{}", text);
+        let (text, _) = machine.disassemble_at(0x104, true).unwrap();
+        assert!(
+            text.starts_with("0x00000104 <thumb_main+0x4>: b"),
+            "{}",
+            text
+        );
+        assert!(text.ends_with(" ; 0x00000100 <thumb_main>"), "{}", text);
+        //不是跳转指令时只标注地址
+        let (text, _) = machine.disassemble_at(0x10, false).unwrap();
+        assert!(!text.contains(" ; "), "{}", text);
+    }
+}
@@ -0,0 +1,29 @@
+use crate::machine::Machine;
+
+//只有一个处理器, 只需要本地监视器, 记录LDREX访问的(地址, 字节数)
+impl Machine {
+    //SetExclusiveMonitors
+    pub fn set_exclusive_monitors(&mut self, address: u32, size: u32) {
+        self.exclusive_monitor = Some((address, size));
+    }
+
+    //ExclusiveMonitorsPass, 不管是否通过都会清除监视器
+    //地址和大小都要和LDREX一致, 不一致时是IMPLEMENTATION DEFINED, 这里当作失败
+    pub fn exclusive_monitors_pass(&mut self, address: u32, size: u32) -> bool {
+        self.exclusive_monitor.take() == Some((address, size))
+    }
+
+    //CLREX, 复位和异常返回时调用
+    pub fn clear_exclusive_local(&mut self) {
+        self.exclusive_monitor = None;
+    }
+
+    //write_memory写入前调用, 普通的存储写到了被监视的地址时清除监视器
+    //架构上是IMPLEMENTATION DEFINED, 这样被改写过的地址上STREX会失败
+    pub fn check_exclusive_write(&mut self, address: u32) {
+        if matches!(self.exclusive_monitor, Some((start, size)) if address.wrapping_sub(start) < size)
+        {
+            self.exclusive_monitor = None;
+        }
+    }
+}
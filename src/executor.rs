@@ -1,31 +1,115 @@
 use bitfield::Bit;
 use bitvec::prelude::*;
+use yaxpeax_arch::LengthedInstruction;
 use yaxpeax_arm::armv7::{Instruction, Opcode, Operand, ShiftStyle};
 
 use crate::arithmetic::*;
 use crate::cpu::{InstrSet, LR_INDEX, PC_INDEX, SP_INDEX};
-use crate::machine::Machine;
+use crate::machine::{HaltReason, Machine};
+use crate::semihosting::SEMIHOSTING_BKPT;
 use crate::vmerror::VMError;
 
 impl Machine {
+    //这些指令不受条件码控制: BKPT总是执行, CBZ/CBNZ本身不能带条件
+    pub fn is_unconditional(opcode: Opcode) -> bool {
+        matches!(opcode, Opcode::BKPT | Opcode::CBNZ | Opcode::CBZ)
+    }
+
+    //严格模式下检查一些常用指令中应为0或者应为1的位, 解码器没有检查这些位
+    //违反时是UNPREDICTABLE, 这里当作未定义指令, 方便发现客户程序的错误
+    pub fn check_reserved_bits(&self, inst: &Instruction) -> Result<(), VMError> {
+        if self.current_instr_set() != InstrSet::Arm {
+            return Ok(());
+        }
+        let word = self.arm_encoding();
+        let valid = match inst.opcode {
+            //P350 P352 (1)(1)(1)(1)(1)(1)(1)(1)(1)(1)(1)(1)
+            Opcode::BX | Opcode::BLX if matches!(inst.operands[0], Operand::Reg(..)) => {
+                word >> 8 & 0xfff == 0xfff
+            }
+            //P362 (1)(1)(1)(1) Rd (1)(1)(1)(1)
+            Opcode::CLZ => word >> 16 & 0xf == 0xf && word >> 8 & 0xf == 0xf,
+            //P502 Rd (0)(0)(0)(0) Rm
+            Opcode::MUL => word >> 12 & 0xf == 0,
+            _ => true,
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(VMError::UndefinedInstruction)
+        }
+    }
+
+    //长乘法(SMULL, UMULL, SMLAL, UMLAL, UMAAL, SMLALD, SMLSLD)的操作数统一是[RdLo, RdHi, Rn, Rm]
+    //返回(RdLo, RdHi, R[n], R[m]), 这些指令都要通过这里取操作数, 避免各自用错下标
+    fn long_multiply_operands(
+        &self,
+        inst: &Instruction,
+    ) -> Result<(Operand, Operand, u32, u32), VMError> {
+        Ok((
+            inst.operands[0],
+            inst.operands[1],
+            self.read(inst.operands[2])?,
+            self.read(inst.operands[3])?,
+        ))
+    }
+
+    //R[dHi]:R[dLo]
+    fn read_long(&self, dlo: Operand, dhi: Operand) -> Result<u64, VMError> {
+        Ok((self.read(dhi)? as u64) << 32 | self.read(dlo)? as u64)
+    }
+
+    fn write_long(&mut self, dlo: Operand, dhi: Operand, value: u64) -> Result<(), VMError> {
+        self.write(dlo, value as u32)?;
+        self.write(dhi, (value >> 32) as u32)
+    }
+
+    //LDM/STM访问的最低地址和写回的基址, 寄存器总是按编号从低到高对应从低到高的地址
+    //add和pre对应IA(增后), IB(增前), DA(减后), DB(减前)
+    pub fn block_addresses(base: u32, registers: u32, add: bool, pre: bool) -> (u32, u32) {
+        let size = 4 * registers.count_ones();
+        match (add, pre) {
+            (true, false) => (base, base.wrapping_add(size)),
+            (true, true) => (base.wrapping_add(4), base.wrapping_add(size)),
+            (false, false) => (
+                base.wrapping_sub(size).wrapping_add(4),
+                base.wrapping_sub(size),
+            ),
+            (false, true) => (base.wrapping_sub(size), base.wrapping_sub(size)),
+        }
+    }
+
+    //STREX的Rd和Rt, Rn相同时是UNPREDICTABLE, 写入的状态会覆盖要存的值或者地址
+    fn check_strex_status(d: Operand, operands: &[Operand]) -> Result<(), VMError> {
+        let Operand::Reg(d) = d else { unreachable!() };
+        let conflict = operands.iter().any(|operand| match *operand {
+            Operand::Reg(reg)
+            | Operand::RegDeref(reg)
+            | Operand::RegDerefPreindexOffset(reg, ..) => reg == d,
+            _ => false,
+        });
+        if conflict {
+            return Err(VMError::Unpredictable);
+        }
+        Ok(())
+    }
+
+    //NOP, YIELD, DBG, CSDB和还没有分配编号的HINT, 都不改变寄存器和标志位
+    //以后要根据提示做调度之类的事情时在这里处理
+    fn execute_hint(&mut self) {}
+
     pub fn execute(&mut self, inst: Instruction) -> Result<(), VMError> {
-        match inst.opcode {
-            Opcode::BKPT => {
-                //TODO BKPT
-                return Ok(());
-            }
-            Opcode::CBNZ | Opcode::CBZ => {
-                let nonzero = inst.opcode == Opcode::CBNZ;
-                let n = self.read(inst.operands[0])?;
-                let m = self.read(inst.operands[1])?; //i32
-                if nonzero != (n == 0) {
-                    self.branch_write_pc(self.cpu.regs[PC_INDEX] + m);
-                }
-                return Ok(());
-            }
-            _ => {}
+        //IT块中的指令不论条件是否满足, 执行后都要推进ITSTATE
+        let in_it_block = self.in_it_block();
+        let result = self.execute_instruction(inst);
+        if in_it_block {
+            self.it_advance();
         }
-        if !self.condition_passed(inst.condition) {
+        result
+    }
+
+    fn execute_instruction(&mut self, inst: Instruction) -> Result<(), VMError> {
+        if !Self::is_unconditional(inst.opcode) && !self.condition_passed(inst.condition) {
             return Ok(());
         }
         match inst.opcode {
@@ -61,10 +145,27 @@ impl Machine {
                     n = inst.operands[1];
                     m = inst.operands[2];
                 }
-                let n = self.read(n)?;
+                let n = match (n, m) {
+                    //Thumb的ADDW/SUBW Rd, PC, #imm12就是ADR, PC要先按4字节对齐, Arm的PC本来就是对齐的
+                    (Operand::Reg(reg), Operand::Imm32(..))
+                        if reg.number() as usize == PC_INDEX =>
+                    {
+                        self.align(self.read(n)?, 4)
+                    }
+                    _ => self.read(n)?,
+                };
+                let is_immediate = matches!(m, Operand::Imm32(..));
                 let (m, mut carry) = self.read_with_carry(m)?;
+                //Thumb的MOVW解码成了MOV, 立即数要从编码重新拼
+                let m = match self.thumb_imm16(inst.len().to_const()) {
+                    Some(imm16) if inst.opcode == Opcode::MOV => imm16,
+                    _ => m,
+                };
                 if let Operand::Nothing = inst.operands[4] {
-                    //carry来自寄存器位移
+                    //carry来自寄存器位移, 或者是立即数展开时的carry
+                    if is_immediate {
+                        carry = self.immediate_carry(inst.len().to_const());
+                    }
                 } else {
                     //carry来自立即数
                     carry = self.read(inst.operands[4])? != 0;
@@ -96,7 +197,7 @@ impl Machine {
                     }
                     //MOV只有两个操作数, 所以根据前面的逻辑 d==n, m才是操作数
                     Opcode::MOV => (m, carry, self.cpu.apsr().v()),
-                    Opcode::MUL => (n * m, self.cpu.apsr().c(), self.cpu.apsr().v()),
+                    Opcode::MUL => (n.wrapping_mul(m), self.cpu.apsr().c(), self.cpu.apsr().v()),
                     //MVN只有两个操作数, 所以根据前面的逻辑 d==n, m才是操作数
                     Opcode::MVN => (!m, carry, self.cpu.apsr().v()),
                     Opcode::ORN => (n | !m, carry, self.cpu.apsr().v()),
@@ -112,7 +213,7 @@ impl Machine {
                         let (result, carry) = shift_c(m, ShiftStyle::ROR, 0, self.cpu.apsr().c());
                         (result, carry, self.cpu.apsr().v())
                     }
-                    Opcode::RSB => add_with_carry(!n, m, false),
+                    Opcode::RSB => add_with_carry(!n, m, true),
                     Opcode::RSC => add_with_carry(!n, m, self.cpu.apsr().c()),
                     Opcode::SBC => add_with_carry(n, !m, self.cpu.apsr().c()),
                     Opcode::SUB => add_with_carry(n, !m, true),
@@ -123,8 +224,13 @@ impl Machine {
                 };
                 let reg_index = reg.number() as usize;
                 if reg_index == PC_INDEX {
-                    //Can only occur for ARM encoding
-                    self.alu_write_pc(result); // setflags is always FALSE here
+                    if inst.s {
+                        //SUBS PC, LR, #imm这类指令是异常返回, 不设置标志位
+                        self.exception_return(result);
+                        return Ok(());
+                    }
+                    //Thumb的MOV PC, Rm也会走到这里, 这时ALUWritePC就是BranchWritePC, 不会切换指令集
+                    self.alu_write_pc(result)?;
                 } else {
                     self.write(d, result)?;
                 }
@@ -138,21 +244,28 @@ impl Machine {
             }
             Opcode::ADR => {
                 let d = inst.operands[0];
-                let n = inst.operands[1];
-                let result = self.align(self.cpu.regs[PC_INDEX], 4) + self.read(n)?;
+                let base = self.align(self.pc_value(), 4);
+                let result = match inst.operands[2] {
+                    //Arm编码的操作数是ADD/SUB Rd, PC, #imm的[Rd, PC, imm], 加还是减要看编码的bit23
+                    Operand::Imm32(imm) if self.arm_encoding() >> 23 & 1 == 1 => {
+                        base.wrapping_add(imm)
+                    }
+                    Operand::Imm32(imm) => base.wrapping_sub(imm),
+                    _ => base.wrapping_add(self.read(inst.operands[1])?),
+                };
                 let Operand::Reg(reg) = d else {
                     unreachable!();
                 };
                 let reg_index = reg.number() as usize;
                 if reg_index == PC_INDEX {
-                    self.alu_write_pc(result);
+                    self.alu_write_pc(result)?;
                 } else {
                     self.write(d, result)?;
                 }
             }
             Opcode::B => {
-                let imm32 = self.read(inst.operands[0])?; //i32
-                self.branch_write_pc(self.cpu.regs[PC_INDEX] + imm32);
+                let target_address = self.branch_target(inst.operands[0])?;
+                self.branch_write_pc(target_address);
             }
             Opcode::BFC => {
                 //将Rd的lsbit..msbit部分清0
@@ -185,10 +298,15 @@ impl Machine {
                     self.write(d, bits.load())?;
                 }
             }
-            Opcode::BKPT => unreachable!(),
+            Opcode::BKPT => {
+                if self.read(inst.operands[0])? == SEMIHOSTING_BKPT {
+                    self.semihosting()?;
+                }
+                //TODO 其它的BKPT
+            }
             Opcode::BL | Opcode::BLX => match inst.operands[0] {
                 Operand::BranchThumbOffset(..) | Operand::BranchOffset(..) => {
-                    let imm32 = self.read(inst.operands[0])?; //i32
+                    let mut target_address = self.branch_target(inst.operands[0])?;
                     //没有流水线, 无需调整
                     self.cpu.regs[LR_INDEX] = self.cpu.regs[PC_INDEX];
                     let target_instr_set = match self.read(inst.operands[4])? {
@@ -196,11 +314,9 @@ impl Machine {
                         0b01 => InstrSet::Thumb,
                         _ => self.current_instr_set(),
                     };
-                    let target_address;
+                    //Align(PC, 4) + imm32, imm32是4的倍数, 所以先加再对齐也一样
                     if let InstrSet::Arm = target_instr_set {
-                        target_address = self.align(self.cpu.regs[PC_INDEX], 4) + imm32;
-                    } else {
-                        target_address = self.cpu.regs[PC_INDEX] + imm32;
+                        target_address = self.align(target_address, 4);
                     }
                     self.select_instr_set(target_instr_set);
                     self.branch_write_pc(target_address);
@@ -209,24 +325,42 @@ impl Machine {
                     let target = self.cpu.regs[reg.number() as usize];
                     //没有流水线, 无需调整
                     self.cpu.regs[LR_INDEX] = self.cpu.regs[PC_INDEX];
-                    self.bw_write_pc(target);
+                    self.bw_write_pc(target)?;
                 }
                 _ => unreachable!(),
             },
-            Opcode::BX => self.bw_write_pc(self.read(inst.operands[0])?),
-            Opcode::BXJ => unimplemented!(), //跳转到Jazelle状态, 但目前只支持Arm和Thumb
-            Opcode::CBNZ | Opcode::CBZ => unreachable!(),
-            Opcode::CDP2(..) => unimplemented!(), //TODO CDP2 协处理器
-            Opcode::CLREX => unimplemented!(),    //TODO CLREX 特权指令
+            Opcode::BX => self.bw_write_pc(self.read(inst.operands[0])?)?,
+            Opcode::BXJ => self.unimplemented(inst.opcode)?, //跳转到Jazelle状态, 但目前只支持Arm和Thumb
+            Opcode::CBNZ | Opcode::CBZ => {
+                //只有Thumb指令, 在IT块中是UNPREDICTABLE, 严格模式下当作未定义指令
+                if self.strict
+                    && (self.current_instr_set() != InstrSet::Thumb || self.in_it_block())
+                {
+                    return Err(VMError::UndefinedInstruction);
+                }
+                let nonzero = inst.opcode == Opcode::CBNZ;
+                let n = self.read(inst.operands[0])?;
+                if nonzero != (n == 0) {
+                    let target_address = self.branch_target(inst.operands[1])?;
+                    self.branch_write_pc(target_address);
+                }
+            }
+            Opcode::CDP2(..) => self.unimplemented(inst.opcode)?, //TODO CDP2 协处理器
+            Opcode::CLREX => self.clear_exclusive_local(),
             Opcode::CLZ => {
                 let d = inst.operands[0];
                 let m = self.read(inst.operands[1])?;
                 self.write(d, m.leading_zeros())?;
             }
             Opcode::CMN | Opcode::CMP => {
-                //inst.operands[0] 是 rd
-                let n = self.read(inst.operands[1])?;
-                let m = self.read(inst.operands[2])?;
+                //Arm编码的operands[0]是rd, Thumb编码没有rd
+                let (n, m) = if let Operand::Nothing = inst.operands[2] {
+                    (inst.operands[0], inst.operands[1])
+                } else {
+                    (inst.operands[1], inst.operands[2])
+                };
+                let n = self.read(n)?;
+                let m = self.read(m)?;
                 let (result, carry, overflow) = match inst.opcode {
                     Opcode::CMN => add_with_carry(n, m, false),
                     Opcode::CMP => add_with_carry(n, !m, true),
@@ -238,17 +372,24 @@ impl Machine {
                 apsr.set_c(carry);
                 apsr.set_v(overflow);
             }
-            Opcode::CPS(..) => unimplemented!(), //TODO CPS P1964 P1966
-            Opcode::CPS_modeonly => unimplemented!(), //TODO
-            Opcode::CSDB => unimplemented!(),    //TODO CSDB
-            Opcode::DBG => unimplemented!(),     //TODO DBG
-            Opcode::DMB => unimplemented!(),     //TODO DMB
-            Opcode::DSB => unimplemented!(),     //TODO DSB
-            Opcode::ENTERX => unimplemented!(),  //跳转到ThumbEE状态, 但目前只支持Arm和Thumb
-            Opcode::ERET => unimplemented!(),    //TODO ERET
-            Opcode::HINT => unimplemented!(),    //TODO HINT
-            Opcode::HVC => unimplemented!(),     //TODO HVC
-            Opcode::ISB => unimplemented!(),     //TODO ISB
+            Opcode::CPS(..) => self.unimplemented(inst.opcode)?, //TODO CPS P1964 P1966
+            Opcode::CPS_modeonly => self.unimplemented(inst.opcode)?, //TODO
+            //没有推测执行, 不需要阻止推测的结果被使用
+            Opcode::CSDB => self.execute_hint(),
+            //调试提示, 没有调试系统
+            Opcode::DBG => self.execute_hint(),
+            //只有一个核并且按顺序执行, 访问本来就是有序的, 当作NOP
+            //电脑要看到写入的数据由DSB保证, 这里不写回外部内存的缓存
+            Opcode::DMB => {}
+            //之后的访问要能看到之前的写入, 外部内存的脏行要写回电脑
+            Opcode::DSB => self.flush_external_cache()?,
+            Opcode::ENTERX => self.unimplemented(inst.opcode)?, //跳转到ThumbEE状态, 但目前只支持Arm和Thumb
+            Opcode::ERET => self.unimplemented(inst.opcode)?,   //TODO ERET
+            //还没有分配的提示编号, 按规范当作NOP
+            Opcode::HINT => self.execute_hint(),
+            Opcode::HVC => self.unimplemented(inst.opcode)?, //TODO HVC
+            //每条指令都是执行前才从内存取的, 没有要丢弃的流水线, 当作NOP
+            Opcode::ISB => {}
             Opcode::IT => {
                 let firstcond = self.read(inst.operands[0])?;
                 let mask = self.read(inst.operands[1])?;
@@ -256,33 +397,42 @@ impl Machine {
                     .it_state_mut()
                     .set_value((firstcond << 4 | mask) as u8);
             }
-            Opcode::Invalid => unimplemented!(),   //TODO Invalid
-            Opcode::LDC(..) => unimplemented!(),   //TODO LDC
-            Opcode::LDC2(..) => unimplemented!(),  //TODO LDC2
-            Opcode::LDC2L(..) => unimplemented!(), //TODO LDC2L,
-            Opcode::LDCL(..) => unimplemented!(),  //TODO LDCL
+            //解码器没有给出有意义的指令
+            Opcode::Invalid => return Err(VMError::UndefinedInstruction),
+            Opcode::LDC(..) => self.unimplemented(inst.opcode)?, //TODO LDC
+            Opcode::LDC2(..) => self.unimplemented(inst.opcode)?, //TODO LDC2
+            Opcode::LDC2L(..) => self.unimplemented(inst.opcode)?, //TODO LDC2L,
+            Opcode::LDCL(..) => self.unimplemented(inst.opcode)?, //TODO LDCL
             Opcode::LDM(add, pre, _wback, _usermode) => {
                 //TODO usermode
-                let add = if add { 1 } else { -1i32 as u32 };
+                //inst.operands[0]一定是RegWBack
+                let Operand::RegWBack(rn, _) = inst.operands[0] else {
+                    unreachable!()
+                };
                 let n = self.read(inst.operands[0])?;
-                let mut address = n + if pre { 4 * add } else { 0 };
                 let registers = self.read(inst.operands[1])?;
-                for i in 0..16 {
+                let (mut address, new_base) = Self::block_addresses(n, registers, add, pre);
+                //和POP一样先全部读出来, 中途出错时寄存器保持不变
+                let mut values = [0; 16];
+                for (i, value) in values.iter_mut().enumerate() {
                     if registers >> i & 1 != 1 {
                         continue;
                     }
-                    if i != PC_INDEX {
-                        self.cpu.regs[i] = self.read_memory_word(address)?;
-                    } else {
-                        self.load_write_pc(self.read_memory_word(address)?);
-                    }
-                    if (registers >> (i + 1)).count_ones() > 0 {
-                        //最后一个不改address
-                        address += 4 * add;
+                    *value = self.read_memory_word(address)?;
+                    address = address.wrapping_add(4);
+                }
+                if registers >> PC_INDEX & 1 == 1 {
+                    self.load_write_pc(values[PC_INDEX])?;
+                }
+                for (i, &value) in values[..PC_INDEX].iter().enumerate() {
+                    if registers >> i & 1 == 1 {
+                        self.cpu.regs[i] = value;
                     }
                 }
-                //inst.operands[0]一定是RegWBack
-                self.write(inst.operands[0], address)?;
+                //Rn在列表中时写回的值是UNKNOWN, 这里保留读到的值
+                if registers >> rn.number() & 1 == 0 {
+                    self.write(inst.operands[0], new_base)?;
+                }
             }
             Opcode::LDR
             | Opcode::LDRB
@@ -297,18 +447,29 @@ impl Machine {
                 //TODO LDRBT LDRHT LDRSBT LDRSHT LDRT
                 let t = inst.operands[0];
                 let address = self.read_address(inst.operands[1])?;
-                let mut word = self.read_memory_word(address)?;
-                match inst.opcode {
-                    Opcode::LDRB | Opcode::LDRBT => word = word & 0xff,
-                    Opcode::LDRH | Opcode::LDRHT => word = word & 0xffff,
-                    Opcode::LDRSB | Opcode::LDRSBT => word = (word & 0xff) as i8 as i32 as u32,
-                    Opcode::LDRSH | Opcode::LDRSHT => word = (word & 0xffff) as i16 as i32 as u32,
-                    _ => {}
+                if self.strict_alignment {
+                    let size = match inst.opcode {
+                        Opcode::LDR | Opcode::LDRT => 4,
+                        Opcode::LDRH | Opcode::LDRHT | Opcode::LDRSH | Opcode::LDRSHT => 2,
+                        _ => 1,
+                    };
+                    self.check_alignment(address, size)?;
                 }
+                //只读取需要的字节, 外部内存和MMIO多读会多出串口通信或副作用
+                let word = match inst.opcode {
+                    Opcode::LDRB | Opcode::LDRBT => self.read_memory(address)? as u32,
+                    Opcode::LDRH | Opcode::LDRHT => self.read_memory_halfword(address)? as u32,
+                    Opcode::LDRSB | Opcode::LDRSBT => {
+                        self.read_memory(address)? as i8 as i32 as u32
+                    }
+                    Opcode::LDRSH | Opcode::LDRSHT => {
+                        self.read_memory_halfword(address)? as i16 as i32 as u32
+                    }
+                    _ => self.read_memory_word(address)?,
+                };
                 let Operand::Reg(reg) = t else { unreachable!() };
-                //无需对齐检查
                 if reg.number() as usize == PC_INDEX {
-                    self.load_write_pc(word);
+                    self.load_write_pc(word)?;
                 } else {
                     self.write(t, word)?;
                 }
@@ -318,24 +479,51 @@ impl Machine {
                 let t = inst.operands[0];
                 let t2 = inst.operands[1];
                 let address = self.read_address(inst.operands[2])?;
+                //不论strict_alignment, 都要求4字节对齐
+                self.check_alignment(address, 4)?;
                 self.write(t, self.read_memory_word(address)?)?;
-                self.write(t2, self.read_memory_word(address + 4)?)?;
+                self.write(t2, self.read_memory_word(address.wrapping_add(4))?)?;
                 self.write(inst.operands[2], address)?;
             }
-            Opcode::LDREX => unimplemented!(),     //TODO LDREX
-            Opcode::LDREXB => unimplemented!(),    //TODO LDREXB
-            Opcode::LDREXD => unimplemented!(),    //TODO LDREXD
-            Opcode::LDREXH => unimplemented!(),    //TODO LDREXH
-            Opcode::LEAVEX => {}                   //跳转到Thumb状态, 但目前只支持Arm和Thumb
-            Opcode::MCR2(..) => unimplemented!(),  //TODO MCR2
-            Opcode::MCRR(..) => unimplemented!(),  //TODO MCRR
-            Opcode::MCRR2(..) => unimplemented!(), //TODO MCRR2
+            Opcode::LDREX | Opcode::LDREXB | Opcode::LDREXH => {
+                let t = inst.operands[0];
+                let address = self.read_address(inst.operands[1])?;
+                let size = match inst.opcode {
+                    Opcode::LDREXB => 1,
+                    Opcode::LDREXH => 2,
+                    _ => 4,
+                };
+                //独占访问总是要求对齐
+                self.check_alignment(address, size)?;
+                self.set_exclusive_monitors(address, size);
+                let value = match size {
+                    1 => self.read_memory(address)? as u32,
+                    2 => self.read_memory_halfword(address)? as u32,
+                    _ => self.read_memory_word(address)?,
+                };
+                self.write(t, value)?;
+            }
+            Opcode::LDREXD => {
+                let t = inst.operands[0];
+                let t2 = inst.operands[1];
+                let address = self.read_address(inst.operands[2])?;
+                self.check_alignment(address, 8)?;
+                self.set_exclusive_monitors(address, 8);
+                let value = self.read_memory_word(address)?;
+                let value2 = self.read_memory_word(address.wrapping_add(4))?;
+                self.write(t, value)?;
+                self.write(t2, value2)?;
+            }
+            Opcode::LEAVEX => {} //跳转到Thumb状态, 但目前只支持Arm和Thumb
+            Opcode::MCR2(..) => self.unimplemented(inst.opcode)?, //TODO MCR2
+            Opcode::MCRR(..) => self.unimplemented(inst.opcode)?, //TODO MCRR
+            Opcode::MCRR2(..) => self.unimplemented(inst.opcode)?, //TODO MCRR2
             Opcode::MLA => {
                 let d = inst.operands[0];
                 let n = self.read(inst.operands[1])?;
                 let m = self.read(inst.operands[2])?;
                 let a = self.read(inst.operands[3])?;
-                let result = n * m + a;
+                let result = n.wrapping_mul(m).wrapping_add(a);
                 self.write(d, result)?;
                 if inst.s {
                     let mut apsr = self.cpu.apsr_mut();
@@ -348,25 +536,35 @@ impl Machine {
                 let n = self.read(inst.operands[1])?;
                 let m = self.read(inst.operands[2])?;
                 let a = self.read(inst.operands[3])?;
-                let result = a - n * m;
+                //不影响标志位
+                let result = a.wrapping_sub(n.wrapping_mul(m));
                 self.write(d, result)?;
             }
             Opcode::MOVT => {
                 let d = inst.operands[0];
-                let imm16 = self.read(inst.operands[1])?;
-                self.write(d, self.read(d)? & (imm16 << 16 | 0xffff))?;
+                let imm16 = match self.thumb_imm16(inst.len().to_const()) {
+                    Some(imm16) => imm16,
+                    None => self.read(inst.operands[1])?,
+                };
+                //只替换高16位, 低16位不变
+                self.write(d, self.read(d)? & 0xffff | imm16 << 16)?;
             }
-            Opcode::MRC2(..) => unimplemented!(),  //TODO MRC2
-            Opcode::MRRC(..) => unimplemented!(),  //TODO MRRC
-            Opcode::MRRC2(..) => unimplemented!(), //TODO MRRC2
+            Opcode::MRC2(..) => self.unimplemented(inst.opcode)?, //TODO MRC2
+            Opcode::MRRC(..) => self.unimplemented(inst.opcode)?, //TODO MRRC
+            Opcode::MRRC2(..) => self.unimplemented(inst.opcode)?, //TODO MRRC2
             //TODO MRS banked register
             Opcode::MRS => self.write(inst.operands[0], self.read(inst.operands[1])?)?,
             //TODO MSR banked register
-            Opcode::MSR => self.write(inst.operands[0], self.read(inst.operands[1])?)?,
-            Opcode::NOP => {
-                //用于断点
-                return Ok(());
+            Opcode::MSR => {
+                let value = match inst.operands[1] {
+                    //P498 MSR(立即数)只有Arm编码, 解码器给出的是没有展开的imm12
+                    Operand::Imm32(imm12) => arm_expand_imm(imm12),
+                    operand => self.read(operand)?,
+                };
+                self.write(inst.operands[0], value)?;
             }
+            //用于断点
+            Opcode::NOP => self.execute_hint(),
             Opcode::PKHBT => {
                 let d = inst.operands[0];
                 let n = self.read(inst.operands[1])?;
@@ -379,22 +577,31 @@ impl Machine {
                 let m = self.read(inst.operands[2])?;
                 self.write(d, n & 0xffff0000 | m & 0xffff)?;
             }
-            Opcode::PLD => unimplemented!(), //TODO PLD
-            Opcode::PLI => unimplemented!(), //TODO PLI
+            Opcode::PLD => self.unimplemented(inst.opcode)?, //TODO PLD
+            Opcode::PLI => self.unimplemented(inst.opcode)?, //TODO PLI
             Opcode::POP => {
                 let mut address = self.cpu.regs[SP_INDEX];
                 let registers = self.read(inst.operands[0])?;
-                // 无需对齐
-                for i in 0..16 {
+                if self.check_stack_alignment && !address.is_multiple_of(4) {
+                    return Err(VMError::AlignmentFault);
+                }
+                //先全部读出来, 中途出错时寄存器保持不变
+                let mut values = [0; 16];
+                for (i, value) in values.iter_mut().enumerate() {
                     if registers >> i & 1 != 1 {
                         continue;
                     }
-                    if i != PC_INDEX {
-                        self.cpu.regs[i] = self.read_memory_word(address)?;
-                    } else {
-                        self.load_write_pc(self.read_memory_word(address)?);
+                    *value = self.read_memory_word(address)?;
+                    address = address.wrapping_add(4);
+                }
+                //先写PC, PC的值不合法时其它寄存器也保持不变
+                if registers >> PC_INDEX & 1 == 1 {
+                    self.load_write_pc(values[PC_INDEX])?;
+                }
+                for (i, &value) in values[..PC_INDEX].iter().enumerate() {
+                    if registers >> i & 1 == 1 {
+                        self.cpu.regs[i] = value;
                     }
-                    address += 4;
                 }
                 self.cpu.regs[SP_INDEX] = address;
             }
@@ -406,7 +613,7 @@ impl Machine {
                     if registers >> i & 1 != 1 {
                         continue;
                     }
-                    address -= 4;
+                    address = address.wrapping_sub(4);
                     self.write_memory_word(address, self.cpu.regs[i])?;
                 }
                 self.cpu.regs[SP_INDEX] = address;
@@ -423,63 +630,27 @@ impl Machine {
             }
             Opcode::QADD16 | Opcode::UQADD16 => {
                 let d = inst.operands[0];
-                let n = self.read(inst.operands[1])?;
-                let m = self.read(inst.operands[2])?;
-                let (sum1, sum2) = match inst.opcode {
-                    Opcode::QADD16 => (
-                        signed_sat(
-                            (n & 0xffff) as u16 as i16 as i64 + (m & 0xffff) as u16 as i16 as i64,
-                            16,
-                        ),
-                        signed_sat(
-                            (n >> 16) as u16 as i16 as i64 + (m >> 16) as u16 as i16 as i64,
-                            16,
-                        ),
-                    ),
-                    Opcode::UQADD16 => (
-                        unsigned_sat((n & 0xffff) as i64 + (m & 0xffff) as i64, 16),
-                        unsigned_sat((n >> 16) as i64 + (m >> 16) as i64, 16),
-                    ),
-                    _ => unreachable!(),
-                };
-                self.write(d, sum2 << 16 | (sum1 & 0xffff))?;
+                let signed = inst.opcode == Opcode::QADD16;
+                let n = halfword_lanes(self.read(inst.operands[1])?, signed);
+                let m = halfword_lanes(self.read(inst.operands[2])?, signed);
+                self.write(d, saturate_halfwords([n[0] + m[0], n[1] + m[1]], signed))?;
             }
             Opcode::QADD8 | Opcode::UQADD8 => {
                 let d = inst.operands[0];
-                let n = self.read(inst.operands[1])?.to_le_bytes();
-                let m = self.read(inst.operands[2])?.to_le_bytes();
-                let mut sum = [0; 4];
-                for i in 0..4 {
-                    sum[i] = match inst.opcode {
-                        Opcode::QADD8 => signed_sat(n[i] as i8 as i64 + m[i] as i8 as i64, 8) as u8,
-                        Opcode::UQADD8 => unsigned_sat(n[i] as i64 + m[i] as i64, 8) as u8,
-                        _ => unreachable!(),
-                    };
-                }
-                self.write(d, u32::from_le_bytes(sum))?;
+                let signed = inst.opcode == Opcode::QADD8;
+                let n = byte_lanes(self.read(inst.operands[1])?, signed);
+                let m = byte_lanes(self.read(inst.operands[2])?, signed);
+                self.write(
+                    d,
+                    saturate_bytes(core::array::from_fn(|i| n[i] + m[i]), signed),
+                )?;
             }
             Opcode::QASX | Opcode::UQASX => {
                 let d = inst.operands[0];
-                let m = self.read(inst.operands[1])?;
-                let n = self.read(inst.operands[2])?;
-                let (diff, sum) = match inst.opcode {
-                    Opcode::QASX => (
-                        signed_sat(
-                            (n & 0xffff) as u16 as i16 as i64 - (m & 0xffff) as u16 as i16 as i64,
-                            16,
-                        ),
-                        signed_sat(
-                            (n >> 16) as u16 as i16 as i64 + (m >> 16) as u16 as i16 as i64,
-                            16,
-                        ),
-                    ),
-                    Opcode::UQASX => (
-                        unsigned_sat((n & 0xffff) as i64 - (m & 0xffff) as i64, 16),
-                        unsigned_sat((n >> 16) as i64 + (m >> 16) as i64, 16),
-                    ),
-                    _ => unreachable!(),
-                };
-                self.write(d, sum << 16 | (diff & 0xffff))?;
+                let signed = inst.opcode == Opcode::QASX;
+                let n = halfword_lanes(self.read(inst.operands[1])?, signed);
+                let m = halfword_lanes(self.read(inst.operands[2])?, signed);
+                self.write(d, saturate_halfwords([n[0] - m[1], n[1] + m[0]], signed))?;
             }
             Opcode::QDADD => {
                 let d = inst.operands[0];
@@ -505,26 +676,10 @@ impl Machine {
             }
             Opcode::QSAX | Opcode::UQSAX => {
                 let d = inst.operands[0];
-                let m = self.read(inst.operands[1])?;
-                let n = self.read(inst.operands[2])?;
-                let (sum, diff) = match inst.opcode {
-                    Opcode::QASX => (
-                        signed_sat(
-                            (n & 0xffff) as u16 as i16 as i64 + (m >> 16) as u16 as i16 as i64,
-                            16,
-                        ),
-                        signed_sat(
-                            (n >> 16) as u16 as i16 as i64 - (m & 0xffff) as u16 as i16 as i64,
-                            16,
-                        ),
-                    ),
-                    Opcode::UQASX => (
-                        unsigned_sat((n & 0xffff) as i64 + (m >> 16) as i64, 16),
-                        unsigned_sat((n >> 16) as i64 - (m & 0xffff) as i64, 16),
-                    ),
-                    _ => unreachable!(),
-                };
-                self.write(d, diff << 16 | (sum & 0xffff))?;
+                let signed = inst.opcode == Opcode::QSAX;
+                let n = halfword_lanes(self.read(inst.operands[1])?, signed);
+                let m = halfword_lanes(self.read(inst.operands[2])?, signed);
+                self.write(d, saturate_halfwords([n[0] + m[1], n[1] - m[0]], signed))?;
             }
             Opcode::QSUB => {
                 let d = inst.operands[0];
@@ -538,40 +693,20 @@ impl Machine {
             }
             Opcode::QSUB16 | Opcode::UQSUB16 => {
                 let d = inst.operands[0];
-                let n = self.read(inst.operands[1])?;
-                let m = self.read(inst.operands[2])?;
-                let (diff1, diff2) = match inst.opcode {
-                    Opcode::QSUB16 => (
-                        signed_sat(
-                            (n & 0xffff) as u16 as i16 as i64 - (m & 0xffff) as u16 as i16 as i64,
-                            16,
-                        ),
-                        signed_sat(
-                            (n >> 16) as u16 as i16 as i64 - (m >> 16) as u16 as i16 as i64,
-                            16,
-                        ),
-                    ),
-                    Opcode::UQSUB16 => (
-                        unsigned_sat((n & 0xffff) as i64 - (m & 0xffff) as i64, 16),
-                        unsigned_sat((n >> 16) as i64 - (m >> 16) as i64, 16),
-                    ),
-                    _ => unreachable!(),
-                };
-                self.write(d, diff2 << 16 | (diff1 & 0xffff))?;
+                let signed = inst.opcode == Opcode::QSUB16;
+                let n = halfword_lanes(self.read(inst.operands[1])?, signed);
+                let m = halfword_lanes(self.read(inst.operands[2])?, signed);
+                self.write(d, saturate_halfwords([n[0] - m[0], n[1] - m[1]], signed))?;
             }
             Opcode::QSUB8 | Opcode::UQSUB8 => {
                 let d = inst.operands[0];
-                let m = self.read(inst.operands[1])?.to_le_bytes();
-                let n = self.read(inst.operands[2])?.to_le_bytes();
-                let mut diff = [0; 4];
-                for i in 0..4 {
-                    diff[i] = match inst.opcode {
-                        Opcode::QSUB8 => signed_sat(n[i] as i8 as i64 - m[i] as i8 as i64, 8) as u8,
-                        Opcode::UQSUB8 => unsigned_sat(n[i] as i64 - m[i] as i64, 8) as u8,
-                        _ => unreachable!(),
-                    };
-                }
-                self.write(d, u32::from_le_bytes(diff))?;
+                let signed = inst.opcode == Opcode::QSUB8;
+                let n = byte_lanes(self.read(inst.operands[1])?, signed);
+                let m = byte_lanes(self.read(inst.operands[2])?, signed);
+                self.write(
+                    d,
+                    saturate_bytes(core::array::from_fn(|i| n[i] - m[i]), signed),
+                )?;
             }
             Opcode::RBIT => {
                 let d = inst.operands[0];
@@ -600,76 +735,68 @@ impl Machine {
                 let high = bytes[1] as u32;
                 self.write(d, low << 8 | high)?;
             }
-            Opcode::RFE(..) => unimplemented!(), //TODO RFE
+            Opcode::RFE(..) => self.unimplemented(inst.opcode)?, //TODO RFE
             Opcode::SADD16 | Opcode::UADD16 => {
                 let d = inst.operands[0];
                 let n = self.read(inst.operands[1])?;
                 let m = self.read(inst.operands[2])?;
-                let sum1 = (n & 0xffff) + (m & 0xffff);
-                let sum2 = (n >> 16) + (m >> 16);
+                let (sum1, sum2, ge1, ge2) = match inst.opcode {
+                    Opcode::SADD16 => {
+                        let sum1 = n as u16 as i16 as i32 + m as u16 as i16 as i32;
+                        let sum2 = (n >> 16) as u16 as i16 as i32 + (m >> 16) as u16 as i16 as i32;
+                        (sum1 as u32, sum2 as u32, sum1 >= 0, sum2 >= 0)
+                    }
+                    Opcode::UADD16 => {
+                        let sum1 = (n & 0xffff) + (m & 0xffff);
+                        let sum2 = (n >> 16) + (m >> 16);
+                        (sum1, sum2, sum1 >= 0x10000, sum2 >= 0x10000)
+                    }
+                    _ => unreachable!(),
+                };
                 self.write(d, sum2 << 16 | (sum1 & 0xffff))?;
-                self.cpu.apsr_mut().set_ge(
-                    if match inst.opcode {
-                        Opcode::SADD16 => sum2 as i32 >= 0,
-                        Opcode::UADD16 => sum2 >= 0x10000,
-                        _ => unreachable!(),
-                    } {
-                        0b11
-                    } else {
-                        0b00
-                    } << 2
-                        | if match inst.opcode {
-                            Opcode::SADD16 => sum1 as i32 >= 0,
-                            Opcode::UADD16 => sum1 >= 0x10000,
-                            _ => unreachable!(),
-                        } {
-                            0b11
-                        } else {
-                            0b00
-                        },
-                );
+                //GE[1:0]只由低半字决定, GE[3:2]只由高半字决定
+                let mut ge = 0;
+                if ge1 {
+                    ge |= 0b0011;
+                }
+                if ge2 {
+                    ge |= 0b1100;
+                }
+                self.cpu.apsr_mut().set_ge(ge);
             }
             Opcode::SADD8 | Opcode::UADD8 => {
                 let d = inst.operands[0];
-                let n = self.read(inst.operands[1])?.to_le_bytes();
-                let m = self.read(inst.operands[2])?.to_le_bytes();
+                let signed = inst.opcode == Opcode::SADD8;
+                let n = byte_lanes(self.read(inst.operands[1])?, signed);
+                let m = byte_lanes(self.read(inst.operands[2])?, signed);
+                let sum: [i32; 4] = core::array::from_fn(|i| n[i] + m[i]);
+                //GE[i]只由第i个字节决定, 有符号时看结果是否>=0, 无符号时看是否进位
                 let mut ge = 0;
-                let mut sum = [0; 4];
-                for i in 0..4 {
-                    sum[i] = n[i] + m[i];
-                    let sum = n[i] as u32 + m[i] as u32;
-                    for i in 0..4 {
-                        if match inst.opcode {
-                            Opcode::SADD8 => sum as i8 >= 0,
-                            Opcode::UADD8 => sum >= 0x100,
-                            _ => unreachable!(),
-                        } {
-                            ge |= 1 << i;
-                        }
+                for (i, &lane) in sum.iter().enumerate() {
+                    if if signed { lane >= 0 } else { lane >= 0x100 } {
+                        ge |= 1 << i;
                     }
                 }
-                self.write(d, u32::from_le_bytes(sum))?;
+                self.write(d, pack_bytes(sum))?;
                 self.cpu.apsr_mut().set_ge(ge);
             }
             Opcode::SASX | Opcode::UASX => {
                 let d = inst.operands[0];
-                let n = self.read(inst.operands[1])?;
-                let m = self.read(inst.operands[2])?;
-                let diff = (n & 0xffff) - (m >> 16);
-                let sum = (n >> 16) + (n & 0xffff);
-                self.write(d, sum << 16 | (diff & 0xffff))?;
-                self.cpu.apsr_mut().set_ge(
-                    if match inst.opcode {
-                        Opcode::SASX => sum as i32 >= 0,
-                        Opcode::UASX => sum >= 0x10000,
-                        _ => unreachable!(),
-                    } {
-                        0b11
-                    } else {
-                        0b00
-                    } << 2
-                        | if diff as i32 >= 0 { 0b11 } else { 0b00 },
-                );
+                let signed = inst.opcode == Opcode::SASX;
+                let n = halfword_lanes(self.read(inst.operands[1])?, signed);
+                let m = halfword_lanes(self.read(inst.operands[2])?, signed);
+                let diff = n[0] - m[1];
+                let sum = n[1] + m[0];
+                self.write(d, pack_halfwords([diff, sum]))?;
+                //GE[1:0]由低半字的差决定, GE[3:2]由高半字的和决定
+                let mut ge = 0;
+                if diff >= 0 {
+                    ge |= 0b0011;
+                }
+                if if signed { sum >= 0 } else { sum >= 0x10000 } {
+                    ge |= 0b1100;
+                }
+                self.cpu.apsr_mut().set_ge(ge);
             }
             Opcode::SBFX | Opcode::UBFX => {
                 let d = inst.operands[0];
@@ -698,7 +825,8 @@ impl Machine {
                     result = 0;
                 } else {
                     result = match inst.opcode {
-                        Opcode::SDIV => (n as i32 / m as i32) as u32,
+                        //INT_MIN / -1溢出, 结果是INT_MIN
+                        Opcode::SDIV => (n as i32).wrapping_div(m as i32) as u32,
                         Opcode::UDIV => n / m,
                         _ => unreachable!(),
                     }
@@ -716,66 +844,62 @@ impl Machine {
                 }
                 self.write(d, u32::from_le_bytes(sum))?;
             }
-            Opcode::SETEND => unimplemented!(), //TODO SETEND
-            Opcode::SEV => unimplemented!(),    //TODO SEV
+            Opcode::SETEND => {
+                let big_endian = self.read(inst.operands[0])? != 0;
+                self.cpu.cpsr.set_e(big_endian);
+            }
+            //只有一个处理器, 只设置自己的事件寄存器
+            Opcode::SEV => self.event_register = true,
+            //半加半减: 通道扩展成有符号数(SH*)或无符号数(UH*)后运算, 结果取[N:1]位
+            //和或差在i32中不会溢出, 算术右移一位后取低位就是[N:1]位
             Opcode::SHADD16 | Opcode::UHADD16 => {
                 let d = inst.operands[0];
-                let n = self.read(inst.operands[1])?;
-                let m = self.read(inst.operands[2])?;
-                let sum1 = (n & 0xffff) + (m & 0xffff);
-                let sum2 = (n >> 16) + (m >> 16);
-                self.write(d, ((sum2 >> 1 & 0xffff) << 16) | (sum1 >> 1 & 0xffff))?;
+                let signed = inst.opcode == Opcode::SHADD16;
+                let n = halfword_lanes(self.read(inst.operands[1])?, signed);
+                let m = halfword_lanes(self.read(inst.operands[2])?, signed);
+                self.write(d, pack_halfwords([(n[0] + m[0]) >> 1, (n[1] + m[1]) >> 1]))?;
             }
             Opcode::SHADD8 | Opcode::UHADD8 => {
                 let d = inst.operands[0];
-                let n = self.read(inst.operands[1])?.to_le_bytes();
-                let m = self.read(inst.operands[2])?.to_le_bytes();
-                let mut sum = [0; 4];
-                for i in 0..4 {
-                    sum[i] = ((n[i] as i32 + m[i] as i32) >> 1 & 0xff) as u8;
-                }
-                self.write(d, u32::from_le_bytes(sum))?;
+                let signed = inst.opcode == Opcode::SHADD8;
+                let n = byte_lanes(self.read(inst.operands[1])?, signed);
+                let m = byte_lanes(self.read(inst.operands[2])?, signed);
+                self.write(d, pack_bytes(core::array::from_fn(|i| (n[i] + m[i]) >> 1)))?;
             }
             Opcode::SHASX | Opcode::UHASX => {
                 let d = inst.operands[0];
-                let n = self.read(inst.operands[1])?;
-                let m = self.read(inst.operands[2])?;
-                let diff = (n & 0xffff) - (m >> 16);
-                let sum = (n >> 16) + (n & 0xffff);
-                self.write(d, ((sum >> 1 & 0xffff) << 16) | (diff >> 1 & 0xffff))?;
+                let signed = inst.opcode == Opcode::SHASX;
+                let n = halfword_lanes(self.read(inst.operands[1])?, signed);
+                let m = halfword_lanes(self.read(inst.operands[2])?, signed);
+                let diff = n[0] - m[1];
+                let sum = n[1] + m[0];
+                self.write(d, pack_halfwords([diff >> 1, sum >> 1]))?;
             }
             Opcode::SHSAX | Opcode::UHSAX => {
                 let d = inst.operands[0];
-                let n = self.read(inst.operands[1])?;
-                let m = self.read(inst.operands[2])?;
-                let sum = (n & 0xffff) + (m >> 16);
-                let diff = (n >> 16) - (n & 0xffff);
-                self.write(d, ((diff >> 1 & 0xffff) << 16) | (sum >> 1 & 0xffff))?;
+                let signed = inst.opcode == Opcode::SHSAX;
+                let n = halfword_lanes(self.read(inst.operands[1])?, signed);
+                let m = halfword_lanes(self.read(inst.operands[2])?, signed);
+                let sum = n[0] + m[1];
+                let diff = n[1] - m[0];
+                self.write(d, pack_halfwords([sum >> 1, diff >> 1]))?;
             }
             Opcode::SHSUB16 | Opcode::UHSUB16 => {
                 let d = inst.operands[0];
-                let n = self.read(inst.operands[1])?;
-                let m = self.read(inst.operands[2])?;
-                let diff1 = (n & 0xffff) - (m & 0xffff);
-                let diff2 = (n >> 16) - (n >> 16);
-                self.write(d, ((diff2 >> 1 & 0xffff) << 16) | (diff1 >> 1 & 0xffff))?;
+                let signed = inst.opcode == Opcode::SHSUB16;
+                let n = halfword_lanes(self.read(inst.operands[1])?, signed);
+                let m = halfword_lanes(self.read(inst.operands[2])?, signed);
+                self.write(d, pack_halfwords([(n[0] - m[0]) >> 1, (n[1] - m[1]) >> 1]))?;
             }
             Opcode::SHSUB8 | Opcode::UHSUB8 => {
                 let d = inst.operands[0];
-                let n = self.read(inst.operands[1])?.to_le_bytes();
-                let m = self.read(inst.operands[2])?.to_le_bytes();
-                let mut diff = [0; 4];
-                for i in 0..4 {
-                    diff[i] = match inst.opcode {
-                        Opcode::SHADD8 => ((n[i] as i32 - m[i] as i32) >> 1 & 0xff) as u8,
-                        Opcode::UHSUB8 => ((n[i] - m[i]) >> 1 & 0xff) as u8,
-                        _ => unreachable!(),
-                    }
-                }
-                self.write(d, u32::from_le_bytes(diff))?;
+                let signed = inst.opcode == Opcode::SHSUB8;
+                let n = byte_lanes(self.read(inst.operands[1])?, signed);
+                let m = byte_lanes(self.read(inst.operands[2])?, signed);
+                self.write(d, pack_bytes(core::array::from_fn(|i| (n[i] - m[i]) >> 1)))?;
             }
-            Opcode::SMAL(..) => unimplemented!(), //TODO SMAL
-            Opcode::SMC => unimplemented!(),      //TODO SMC
+            Opcode::SMAL(..) => self.unimplemented(inst.opcode)?, //TODO SMAL
+            Opcode::SMC => self.unimplemented(inst.opcode)?,      //TODO SMC
             Opcode::SMLA(n_high, m_high) => {
                 let d = inst.operands[0];
                 let n = self.read(inst.operands[1])?;
@@ -791,58 +915,40 @@ impl Machine {
             }
             Opcode::SMLAD(m_swap) => {
                 let d = inst.operands[0];
-                let n = self.read(inst.operands[1])? as i64;
+                let n = self.read(inst.operands[1])?;
                 let m = self.read(inst.operands[2])?;
-                let a = self.read(inst.operands[3])? as i64;
-                let operand2 = if m_swap { rotate_right(m, 16) } else { m } as i64;
-                let product1 = (n & 0xffff) * (operand2 & 0xffff);
-                let product2 = (n >> 16) * (operand2 >> 16);
+                let a = self.read(inst.operands[3])? as i32 as i64;
+                let (product1, product2) = dual_products(n, m, m_swap);
                 let result = product1 + product2 + a;
                 self.write(d, result as u32)?;
-                if result >> 32 != 0 {
+                if overflows_i32(result) {
                     self.cpu.apsr_mut().set_q(true);
                 }
             }
             Opcode::SMLAL | Opcode::UMLAL => {
-                let dlo = inst.operands[0];
-                let dhi = inst.operands[1];
-                let n = self.read(inst.operands[2])?;
-                let m = self.read(inst.operands[3])?;
+                let (dlo, dhi, n, m) = self.long_multiply_operands(&inst)?;
+                let accumulate = self.read_long(dlo, dhi)?;
                 let result = match inst.opcode {
-                    Opcode::SMLAL => {
-                        (n as i64 * m as i64
-                            + ((self.read(dhi)? as i64) << 32 | self.read(dlo)? as i64))
-                            as u64
-                    }
-                    Opcode::UMLAL => {
-                        n as u64 * m as u64
-                            + ((self.read(dhi)? as u64) << 32 | self.read(dlo)? as u64)
-                    }
+                    Opcode::SMLAL => ((n as i32 as i64).wrapping_mul(m as i32 as i64) as u64)
+                        .wrapping_add(accumulate),
+                    Opcode::UMLAL => (n as u64 * m as u64).wrapping_add(accumulate),
                     _ => unreachable!(),
                 };
-                self.write(dlo, (result & 0xffffffff) as u32)?;
-                self.write(dhi, (result >> 32) as u32)?;
+                self.write_long(dlo, dhi, result)?;
                 if inst.s {
                     let mut apsr = self.cpu.apsr_mut();
                     apsr.set_n(result >> 63 & 1 == 1);
-                    apsr.set_z(result > 0);
+                    apsr.set_z(result == 0);
                 }
             }
             Opcode::SMLALD(m_swap) => {
-                let dlo = inst.operands[0];
-                let dhi = inst.operands[1];
-                let n = self.read(inst.operands[2])? as i64;
-                let m = self.read(inst.operands[3])?;
-                let operand2 = if m_swap { rotate_right(m, 16) } else { m } as i64;
-                let product1 = (n & 0xffff) * (operand2 & 0xffff);
-                let product2 = (n >> 16) * (operand2 >> 16);
-                let result = product1
-                    + product2
-                    + ((self.read(dhi)? as u64) << 32 | self.read(dlo)? as u64) as i64;
-                self.write(dlo, (result & 0xffffffff) as u32)?;
-                self.write(dhi, (result >> 32) as u32)?;
-            }
-            Opcode::SMLAL_halfword(..) => unimplemented!(), //TODO SMLAL_halfword
+                let (dlo, dhi, n, m) = self.long_multiply_operands(&inst)?;
+                let (product1, product2) = dual_products(n, m, m_swap);
+                //64位的累加按补码回绕
+                let result = (product1 + product2).wrapping_add(self.read_long(dlo, dhi)? as i64);
+                self.write_long(dlo, dhi, result as u64)?;
+            }
+            Opcode::SMLAL_halfword(..) => self.unimplemented(inst.opcode)?, //TODO SMLAL_halfword
             Opcode::SMLAW(m_high) => {
                 let d = inst.operands[0];
                 let n = self.read(inst.operands[1])? as i64;
@@ -857,57 +963,50 @@ impl Machine {
             }
             Opcode::SMLSD(m_swap) => {
                 let d = inst.operands[0];
-                let n = self.read(inst.operands[1])? as i64;
+                let n = self.read(inst.operands[1])?;
                 let m = self.read(inst.operands[2])?;
-                let a = self.read(inst.operands[3])? as i64;
-                let operand2 = if m_swap { rotate_right(m, 16) } else { m } as i64;
-                let product1 = (n & 0xffff) * (operand2 & 0xffff);
-                let product2 = (n >> 16) * (operand2 >> 16);
+                let a = self.read(inst.operands[3])? as i32 as i64;
+                let (product1, product2) = dual_products(n, m, m_swap);
                 let result = product1 - product2 + a;
                 self.write(d, result as u32)?;
-                if result >> 32 != 0 {
+                if overflows_i32(result) {
                     self.cpu.apsr_mut().set_q(true);
                 }
             }
             Opcode::SMLSLD(m_swap) => {
-                let dlo = inst.operands[0];
-                let dhi = inst.operands[1];
-                let n = self.read(inst.operands[2])? as i64;
-                let m = self.read(inst.operands[3])?;
-                let operand2 = if m_swap { rotate_right(m, 16) } else { m } as i64;
-                let product1 = (n & 0xffff) * (operand2 & 0xffff);
-                let product2 = (n >> 16) * (operand2 >> 16);
-                let result = product1 - product2
-                    + ((self.read(dhi)? as u64) << 32 | self.read(dlo)? as u64) as i64;
-                self.write(dlo, (result & 0xffffffff) as u32)?;
-                self.write(dhi, (result >> 32) as u32)?;
+                let (dlo, dhi, n, m) = self.long_multiply_operands(&inst)?;
+                let (product1, product2) = dual_products(n, m, m_swap);
+                //64位的累加按补码回绕
+                let result = (product1 - product2).wrapping_add(self.read_long(dlo, dhi)? as i64);
+                self.write_long(dlo, dhi, result as u64)?;
             }
             Opcode::SMMLA(round) => {
                 let d = inst.operands[0];
-                let n = self.read(inst.operands[1])? as i64;
-                let m = self.read(inst.operands[2])? as i64;
-                let a = self.read(inst.operands[3])? as i64;
-                let mut result = (a << 32) + n * m;
+                //有符号数的乘积不会超出i64, 加上累加值后只需要[63:32]位, 按64位回绕
+                let n = self.read(inst.operands[1])? as i32 as i64;
+                let m = self.read(inst.operands[2])? as i32 as i64;
+                let a = self.read(inst.operands[3])? as i32 as i64;
+                let mut result = (a << 32).wrapping_add(n * m);
                 if round {
-                    result += 0x80000000;
+                    result = result.wrapping_add(0x80000000);
                 }
                 self.write(d, (result >> 32) as u32)?;
             }
             Opcode::SMMLS(round) => {
                 let d = inst.operands[0];
-                let n = self.read(inst.operands[1])? as i64;
-                let m = self.read(inst.operands[2])? as i64;
-                let a = self.read(inst.operands[3])? as i64;
-                let mut result = (a << 32) - n * m;
+                let n = self.read(inst.operands[1])? as i32 as i64;
+                let m = self.read(inst.operands[2])? as i32 as i64;
+                let a = self.read(inst.operands[3])? as i32 as i64;
+                let mut result = (a << 32).wrapping_sub(n * m);
                 if round {
-                    result += 0x80000000;
+                    result = result.wrapping_add(0x80000000);
                 }
                 self.write(d, (result >> 32) as u32)?;
             }
             Opcode::SMMUL(round) => {
                 let d = inst.operands[0];
-                let n = self.read(inst.operands[1])? as i64;
-                let m = self.read(inst.operands[2])? as i64;
+                let n = self.read(inst.operands[1])? as i32 as i64;
+                let m = self.read(inst.operands[2])? as i32 as i64;
                 let mut result = n * m;
                 if round {
                     result += 0x80000000;
@@ -916,14 +1015,12 @@ impl Machine {
             }
             Opcode::SMUAD(m_swap) => {
                 let d = inst.operands[0];
-                let n = self.read(inst.operands[1])? as i64;
+                let n = self.read(inst.operands[1])?;
                 let m = self.read(inst.operands[2])?;
-                let operand2 = if m_swap { rotate_right(m, 16) } else { m } as i64;
-                let product1 = (n & 0xffff) * (operand2 & 0xffff);
-                let product2 = (n >> 16) * (operand2 >> 16);
+                let (product1, product2) = dual_products(n, m, m_swap);
                 let result = product1 + product2;
                 self.write(d, result as u32)?;
-                if result >> 32 != 0 {
+                if overflows_i32(result) {
                     self.cpu.apsr_mut().set_q(true);
                 }
             }
@@ -937,21 +1034,17 @@ impl Machine {
                 self.write(d, result as u32)?;
             }
             Opcode::SMULL | Opcode::UMULL => {
-                let dlo = inst.operands[0];
-                let dhi = inst.operands[1];
-                let n = self.read(inst.operands[1])?;
-                let m = self.read(inst.operands[2])?;
+                let (dlo, dhi, n, m) = self.long_multiply_operands(&inst)?;
                 let result = match inst.opcode {
-                    Opcode::SMULL => (n as i64 * m as i64) as u64,
+                    Opcode::SMULL => (n as i32 as i64 * m as i32 as i64) as u64,
                     Opcode::UMULL => n as u64 * m as u64,
                     _ => unreachable!(),
                 };
-                self.write(dlo, (result & 0xffffffff) as u32)?;
-                self.write(dhi, (result >> 32) as u32)?;
+                self.write_long(dlo, dhi, result)?;
                 if inst.s {
                     let mut apsr = self.cpu.apsr_mut();
                     apsr.set_n(result >> 63 & 1 == 1);
-                    apsr.set_z(result > 0);
+                    apsr.set_z(result == 0);
                 }
             }
             Opcode::SMULW(m_high) => {
@@ -964,21 +1057,25 @@ impl Machine {
             }
             Opcode::SMUSD(m_swap) => {
                 let d = inst.operands[0];
-                let n = self.read(inst.operands[1])? as i64;
+                let n = self.read(inst.operands[1])?;
                 let m = self.read(inst.operands[2])?;
-                let operand2 = if m_swap { rotate_right(m, 16) } else { m } as i64;
-                let product1 = (n & 0xffff) * (operand2 & 0xffff);
-                let product2 = (n >> 16) * (operand2 >> 16);
+                let (product1, product2) = dual_products(n, m, m_swap);
+                //两个乘积的差不会超出32位, 不影响Q
                 let result = product1 - product2;
                 self.write(d, result as u32)?;
             }
-            Opcode::SRS(..) => unimplemented!(), //TODO SRS
+            Opcode::SRS(..) => self.unimplemented(inst.opcode)?, //TODO SRS
             Opcode::SSAT | Opcode::USAT => {
                 let d = inst.operands[0];
                 let saturate_to = self.read(inst.operands[1])?
                     + if let Opcode::SSAT = inst.opcode { 1 } else { 0 };
+                //operands[2]带着可选的移位, read会先移位
                 let n = self.read(inst.operands[2])?;
-                let (result, sat) = signed_sat_q(n as i32 as i64, saturate_to);
+                let (result, sat) = sat_q(
+                    n as i32 as i64,
+                    saturate_to,
+                    matches!(inst.opcode, Opcode::USAT),
+                );
                 self.write(d, result)?;
                 if sat {
                     self.cpu.apsr_mut().set_q(true);
@@ -1031,76 +1128,83 @@ impl Machine {
             }
             Opcode::SSAX | Opcode::USAX => {
                 let d = inst.operands[0];
-                let n = self.read(inst.operands[1])?;
-                let m = self.read(inst.operands[2])?;
-                let sum = (n & 0xffff) + (m >> 16);
-                let diff = (n >> 16) - (n & 0xffff);
-                self.write(d, (diff << 16) | (sum & 0xffff))?;
-                self.cpu.apsr_mut().set_ge(
-                    if diff as i32 >= 0 { 0b11 } else { 0b00 } << 2
-                        | if match inst.opcode {
-                            Opcode::SSAX => sum as i32 >= 0,
-                            Opcode::USAX => sum >= 0x10000,
-                            _ => unreachable!(),
-                        } {
-                            0b11
-                        } else {
-                            0b00
-                        },
-                );
+                let signed = inst.opcode == Opcode::SSAX;
+                let n = halfword_lanes(self.read(inst.operands[1])?, signed);
+                let m = halfword_lanes(self.read(inst.operands[2])?, signed);
+                let sum = n[0] + m[1];
+                let diff = n[1] - m[0];
+                self.write(d, pack_halfwords([sum, diff]))?;
+                //GE[1:0]由低半字的和决定, GE[3:2]由高半字的差决定
+                let mut ge = 0;
+                if if signed { sum >= 0 } else { sum >= 0x10000 } {
+                    ge |= 0b0011;
+                }
+                if diff >= 0 {
+                    ge |= 0b1100;
+                }
+                self.cpu.apsr_mut().set_ge(ge);
             }
             Opcode::SSUB16 | Opcode::USUB16 => {
                 let d = inst.operands[0];
-                let n = self.read(inst.operands[1])?;
-                let m = self.read(inst.operands[2])?;
-                let diff1 = (n & 0xffff) - (m & 0xffff);
-                let diff2 = (n >> 16) - (n >> 16);
-                self.write(d, ((diff2 >> 1 & 0xffff) << 16) | (diff1 >> 1 & 0xffff))?;
-                self.cpu.apsr_mut().set_ge(
-                    (if diff2 as i32 >= 0 { 0b11 } else { 0b00 }) << 2
-                        | if diff1 as i32 >= 0 { 0b11 } else { 0b00 },
-                );
+                let signed = inst.opcode == Opcode::SSUB16;
+                let n = halfword_lanes(self.read(inst.operands[1])?, signed);
+                let m = halfword_lanes(self.read(inst.operands[2])?, signed);
+                let diff = [n[0] - m[0], n[1] - m[1]];
+                self.write(d, pack_halfwords(diff))?;
+                //无符号时结果>=0就是没有借位
+                let mut ge = 0;
+                if diff[0] >= 0 {
+                    ge |= 0b0011;
+                }
+                if diff[1] >= 0 {
+                    ge |= 0b1100;
+                }
+                self.cpu.apsr_mut().set_ge(ge);
             }
             Opcode::SSUB8 | Opcode::USUB8 => {
                 let d = inst.operands[0];
-                let n = self.read(inst.operands[1])?.to_le_bytes();
-                let m = self.read(inst.operands[2])?.to_le_bytes();
-                let mut diff = [0; 4];
+                let signed = inst.opcode == Opcode::SSUB8;
+                let n = byte_lanes(self.read(inst.operands[1])?, signed);
+                let m = byte_lanes(self.read(inst.operands[2])?, signed);
+                let diff: [i32; 4] = core::array::from_fn(|i| n[i] - m[i]);
+                //无符号时结果>=0就是没有借位
                 let mut ge = 0;
-                for i in 0..4 {
-                    let x = n[i] as i32 - m[i] as i32;
-                    diff[i] = x as u8;
-                    for i in 0..4 {
-                        if x >= 0 {
-                            ge |= 1 << i;
-                        }
+                for (i, &lane) in diff.iter().enumerate() {
+                    if lane >= 0 {
+                        ge |= 1 << i;
                     }
                 }
-                self.write(d, u32::from_le_bytes(diff))?;
+                self.write(d, pack_bytes(diff))?;
                 self.cpu.apsr_mut().set_ge(ge);
             }
-            Opcode::STC(..) => unimplemented!(),   //TODO STC
-            Opcode::STC2(..) => unimplemented!(),  //TODO STC2
-            Opcode::STC2L(..) => unimplemented!(), //TODO STC2L
-            Opcode::STCL(..) => unimplemented!(),  //TODO STCL
+            Opcode::STC(..) => self.unimplemented(inst.opcode)?, //TODO STC
+            Opcode::STC2(..) => self.unimplemented(inst.opcode)?, //TODO STC2
+            Opcode::STC2L(..) => self.unimplemented(inst.opcode)?, //TODO STC2L
+            Opcode::STCL(..) => self.unimplemented(inst.opcode)?, //TODO STCL
             Opcode::STM(add, pre, _wback, _usermode) => {
                 //TODO usermode
-                let add = if add { 1 } else { -1i32 as u32 };
+                //inst.operands[0]一定是RegWBack
+                let Operand::RegWBack(rn, wback) = inst.operands[0] else {
+                    unreachable!()
+                };
                 let n = self.read(inst.operands[0])?;
-                let mut address = n + if pre { 4 * add } else { 0 };
                 let registers = self.read(inst.operands[1])?;
+                let (mut address, new_base) = Self::block_addresses(n, registers, add, pre);
+                //Rn在列表中且要写回时, 只有Rn是编号最小的寄存器才存原来的值, 否则是UNKNOWN, 这里存写回后的值
+                let lowest = registers.trailing_zeros() as usize;
                 for i in 0..16 {
                     if registers >> i & 1 != 1 {
                         continue;
                     }
-                    self.write_memory_word(address, self.cpu.regs[i])?;
-                    if (registers >> (i + 1)).count_ones() > 0 {
-                        //最后一个不改address
-                        address += 4 * add;
-                    }
+                    let value = if i == rn.number() as usize && wback && i != lowest {
+                        new_base
+                    } else {
+                        self.cpu.regs[i]
+                    };
+                    self.write_memory_word(address, value)?;
+                    address = address.wrapping_add(4);
                 }
-                //inst.operands[0]一定是RegWBack
-                self.write(inst.operands[0], address)?;
+                self.write(inst.operands[0], new_base)?;
             }
             Opcode::STR
             | Opcode::STRB
@@ -1112,7 +1216,14 @@ impl Machine {
                 let t = inst.operands[0];
                 let address = self.read_address(inst.operands[1])?;
                 let word = self.read(t)?;
-                //无需对齐检查
+                if self.strict_alignment {
+                    let size = match inst.opcode {
+                        Opcode::STR | Opcode::STRT => 4,
+                        Opcode::STRH | Opcode::STRHT => 2,
+                        _ => 1,
+                    };
+                    self.check_alignment(address, size)?;
+                }
                 match inst.opcode {
                     Opcode::STR | Opcode::STRT => self.write_memory_word(address, word)?,
                     Opcode::STRB | Opcode::STRBT => {
@@ -1129,15 +1240,58 @@ impl Machine {
                 let t = self.read(inst.operands[0])?;
                 let t2 = self.read(inst.operands[1])?;
                 let address = self.read_address(inst.operands[2])?;
+                //不论strict_alignment, 都要求4字节对齐
+                self.check_alignment(address, 4)?;
                 self.write_memory_word(address, t)?;
-                self.write_memory_word(address + 4, t2)?;
+                self.write_memory_word(address.wrapping_add(4), t2)?;
                 self.write(inst.operands[2], address)?;
             }
-            Opcode::STREX => unimplemented!(),  //TODO STREX
-            Opcode::STREXB => unimplemented!(), //TODO STREXB
-            Opcode::STREXD => unimplemented!(), //TODO STREXD
-            Opcode::STREXH => unimplemented!(), //TODO STREXH
-            Opcode::SVC => unimplemented!(),    //TODO SVC
+            Opcode::STREX | Opcode::STREXB | Opcode::STREXH => {
+                let d = inst.operands[0];
+                Self::check_strex_status(d, &inst.operands[1..])?;
+                let t = self.read(inst.operands[1])?;
+                let address = self.read_address(inst.operands[2])?;
+                let size = match inst.opcode {
+                    Opcode::STREXB => 1,
+                    Opcode::STREXH => 2,
+                    _ => 4,
+                };
+                self.check_alignment(address, size)?;
+                //成功时写入并返回0, 监视器已经被清除时不写入, 返回1
+                if self.exclusive_monitors_pass(address, size) {
+                    match size {
+                        1 => self.write_memory(address, t as u8)?,
+                        2 => self.write_memory_halfword(address, t as u16)?,
+                        _ => self.write_memory_word(address, t)?,
+                    }
+                    self.write(d, 0)?;
+                } else {
+                    self.write(d, 1)?;
+                }
+            }
+            Opcode::STREXD => {
+                let d = inst.operands[0];
+                Self::check_strex_status(d, &inst.operands[1..])?;
+                let t = self.read(inst.operands[1])?;
+                let t2 = self.read(inst.operands[2])?;
+                let address = self.read_address(inst.operands[3])?;
+                self.check_alignment(address, 8)?;
+                if self.exclusive_monitors_pass(address, 8) {
+                    self.write_memory_word(address, t)?;
+                    self.write_memory_word(address.wrapping_add(4), t2)?;
+                    self.write(d, 0)?;
+                } else {
+                    self.write(d, 1)?;
+                }
+            }
+            //TODO 还没有异常向量表, 不进入Supervisor模式, 直接交给svc_handler
+            //解码器目前只能解出Thumb的SVC
+            Opcode::SVC => {
+                let imm = self.read(inst.operands[0])?;
+                if let Some(svc_handler) = self.svc_handler {
+                    svc_handler(self, imm)?;
+                }
+            }
             Opcode::SWP | Opcode::SWPB => {
                 let t = inst.operands[0];
                 let t2 = self.read(inst.operands[1])?;
@@ -1166,20 +1320,25 @@ impl Machine {
                 self.write(
                     d,
                     match inst.opcode {
-                        Opcode::SXTAB => n + rotated[0] as i8 as i32 as u32,
+                        //加法按32位(或每个16位)回绕
+                        Opcode::SXTAB => n.wrapping_add(rotated[0] as i8 as i32 as u32),
                         Opcode::SXTAB16 => {
-                            ((n >> 16) + rotated[2] as i8 as i32 as u32) << 16
-                                | ((n & 0xffff) + rotated[0] as i8 as i32 as u32)
+                            ((n >> 16).wrapping_add(rotated[2] as i8 as i32 as u32) & 0xffff) << 16
+                                | ((n & 0xffff).wrapping_add(rotated[0] as i8 as i32 as u32)
+                                    & 0xffff)
                         }
-                        Opcode::SXTAH => {
-                            n + i16::from_le_bytes([rotated[0], rotated[1]]) as i32 as u32
-                        }
-                        Opcode::UXTAB => n + rotated[0] as u32,
+                        //取旋转后的低16位
+                        Opcode::SXTAH => n.wrapping_add(
+                            i16::from_le_bytes([rotated[0], rotated[1]]) as i32 as u32,
+                        ),
+                        Opcode::UXTAB => n.wrapping_add(rotated[0] as u32),
                         Opcode::UXTAB16 => {
-                            ((n >> 16) + rotated[2] as u32) << 16
-                                | ((n & 0xffff) + rotated[0] as u32)
+                            ((n >> 16).wrapping_add(rotated[2] as u32) & 0xffff) << 16
+                                | ((n & 0xffff).wrapping_add(rotated[0] as u32) & 0xffff)
+                        }
+                        Opcode::UXTAH => {
+                            n.wrapping_add(u16::from_le_bytes([rotated[0], rotated[1]]) as u32)
                         }
-                        Opcode::UXTAH => n + u16::from_le_bytes([rotated[0], rotated[1]]) as u32,
                         _ => unreachable!(),
                     },
                 )?;
@@ -1216,18 +1375,28 @@ impl Machine {
             Opcode::TBB => {
                 let address = self.read(inst.operands[0])?;
                 let halfwords = self.read_memory(address)? as u32;
-                self.branch_write_pc(self.cpu.regs[PC_INDEX] + 2 * halfwords);
+                self.branch_write_pc(self.cpu.regs[PC_INDEX].wrapping_add(2 * halfwords));
             }
             Opcode::TBH => {
                 let address = self.read(inst.operands[0])?;
                 let halfwords = self.read_memory_halfword(address)? as u32;
-                self.branch_write_pc(self.cpu.regs[PC_INDEX] + 2 * halfwords);
+                self.branch_write_pc(self.cpu.regs[PC_INDEX].wrapping_add(2 * halfwords));
             }
             Opcode::TEQ => {
-                let n = self.read(inst.operands[0])?;
-                let (m, mut carry) = self.read_with_carry(inst.operands[1])?;
+                //Arm编码的operands[0]是rd, Thumb编码没有rd
+                let (n, m) = if let Operand::Nothing = inst.operands[2] {
+                    (inst.operands[0], inst.operands[1])
+                } else {
+                    (inst.operands[1], inst.operands[2])
+                };
+                let n = self.read(n)?;
+                let is_immediate = matches!(m, Operand::Imm32(..));
+                let (m, mut carry) = self.read_with_carry(m)?;
                 if let Operand::Nothing = inst.operands[4] {
-                    //carry来自寄存器位移
+                    //carry来自寄存器位移, 或者是立即数展开时的carry
+                    if is_immediate {
+                        carry = self.immediate_carry(inst.len().to_const());
+                    }
                 } else {
                     //carry来自立即数
                     carry = self.read(inst.operands[4])? != 0;
@@ -1239,10 +1408,20 @@ impl Machine {
                 apsr.set_c(carry);
             }
             Opcode::TST => {
-                let n = self.read(inst.operands[0])?;
-                let (m, mut carry) = self.read_with_carry(inst.operands[1])?;
+                //Arm编码的operands[0]是rd, Thumb编码没有rd
+                let (n, m) = if let Operand::Nothing = inst.operands[2] {
+                    (inst.operands[0], inst.operands[1])
+                } else {
+                    (inst.operands[1], inst.operands[2])
+                };
+                let n = self.read(n)?;
+                let is_immediate = matches!(m, Operand::Imm32(..));
+                let (m, mut carry) = self.read_with_carry(m)?;
                 if let Operand::Nothing = inst.operands[4] {
-                    //carry来自寄存器位移
+                    //carry来自寄存器位移, 或者是立即数展开时的carry
+                    if is_immediate {
+                        carry = self.immediate_carry(inst.len().to_const());
+                    }
                 } else {
                     //carry来自立即数
                     carry = self.read(inst.operands[4])? != 0;
@@ -1253,15 +1432,15 @@ impl Machine {
                 apsr.set_z(result == 0);
                 apsr.set_c(carry);
             }
-            Opcode::UDF => unimplemented!(), //TODO UDF
+            //永久未定义的指令, 总是报UndefinedInstruction
+            Opcode::UDF => return Err(VMError::UndefinedInstruction),
             Opcode::UMAAL => {
-                let dlo = inst.operands[0];
-                let dhi = inst.operands[1];
-                let n = self.read(inst.operands[2])? as u64;
-                let m = self.read(inst.operands[3])? as u64;
-                let result = n * m + self.read(dlo)? as u64 + self.read(dhi)? as u64;
-                self.write(dlo, (result & 0xffffffff) as u32)?;
-                self.write(dhi, (result >> 32) as u32)?;
+                let (dlo, dhi, n, m) = self.long_multiply_operands(&inst)?;
+                //最大为(2^32-1)^2+2*(2^32-1)=2^64-1, 不会溢出; 不影响标志位
+                let result = (n as u64 * m as u64)
+                    .wrapping_add(self.read(dlo)? as u64)
+                    .wrapping_add(self.read(dhi)? as u64);
+                self.write_long(dlo, dhi, result)?;
             }
             Opcode::USAD8 | Opcode::USADA8 => {
                 let d = inst.operands[0];
@@ -1273,14 +1452,80 @@ impl Machine {
                     _ => unreachable!(),
                 };
                 for i in 0..4 {
-                    result += (n[i] as i32 - m[i] as i32).abs() as u32;
+                    result = result.wrapping_add((n[i] as i32 - m[i] as i32).unsigned_abs());
                 }
                 self.write(d, result)?;
             }
-            Opcode::WFE => unimplemented!(),   //TODO WFE
-            Opcode::WFI => unimplemented!(),   //TODO WFI
-            Opcode::YIELD => unimplemented!(), //TODO YIELD
+            //事件寄存器已置位时清除它并继续; 否则应该等待事件
+            //还没有中断之类的事件源, 默认直接继续, 设置了halt_on_wfi时停下交给电脑处理
+            Opcode::WFE => {
+                if !self.event_register && self.halt_on_wfi {
+                    self.halt_reason = Some(HaltReason::WaitForInterrupt);
+                }
+                self.event_register = false;
+            }
+            //还没有中断, 等待中断默认当作NOP, 设置了halt_on_wfi时停下, 继续运行时从下一条指令开始
+            Opcode::WFI => {
+                if self.halt_on_wfi {
+                    self.halt_reason = Some(HaltReason::WaitForInterrupt);
+                }
+            }
+            //只有一个线程, 不需要让出
+            Opcode::YIELD => self.execute_hint(),
         }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::machine::tests::{arm_machine, thumb_machine};
+    use crate::vmerror::VMError;
+
+    #[test]
+    fn strict_rejects_nonzero_should_be_zero_bits() {
+        let code = [
+            0xe0001291, //mul r0, r1, r2, 但SBZ的位不是0
+        ];
+        let mut machine = arm_machine(&code);
+        machine.cpu.regs[1] = 3;
+        machine.cpu.regs[2] = 5;
+        machine.step().unwrap();
+        assert_eq!(machine.cpu.regs[0], 15);
+
+        let mut machine = arm_machine(&code);
+        machine.strict = true;
+        assert!(matches!(machine.step(), Err(VMError::UndefinedInstruction)));
+        assert_eq!(machine.cpu.regs[0], 0);
+    }
+
+    #[test]
+    fn unaligned_ldrd_faults() {
+        let code = [
+            0xe1c020d0, //ldrd r2, r3, [r0]
+        ];
+        let mut machine = arm_machine(&code);
+        machine.memory.data[0x104..0x10c].copy_from_slice(&[1, 0, 0, 0, 2, 0, 0, 0]);
+        machine.cpu.regs[0] = 0x104;
+        machine.step().unwrap();
+        assert_eq!(machine.cpu.regs[2..4], [1, 2]);
+
+        //没有开启strict_alignment也要求4字节对齐
+        let mut machine = arm_machine(&code);
+        machine.cpu.regs[0] = 0x102;
+        assert!(matches!(machine.step(), Err(VMError::AlignmentFault)));
+        assert_eq!(machine.cpu.regs[2..4], [0, 0]);
+    }
+
+    #[test]
+    fn udf_is_undefined_instruction() {
+        let mut machine = arm_machine(&[
+            0xe7f000f0, //udf #0
+        ]);
+        assert!(matches!(machine.step(), Err(VMError::UndefinedInstruction)));
+        let mut machine = thumb_machine(&[
+            0xde00, //udf #0
+        ]);
+        assert!(matches!(machine.step(), Err(VMError::UndefinedInstruction)));
+    }
+}
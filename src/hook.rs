@@ -0,0 +1,137 @@
+use crate::{cpu::LR_INDEX, machine::Machine, vmerror::VMError};
+
+//函数钩子表的大小
+pub const MAX_FUNCTION_HOOKS: usize = 8;
+
+//在电脑(或单片机)上模拟客户程序里的函数, 可以读写寄存器和内存
+//返回后相当于执行了BX LR
+pub type FunctionHook = fn(&mut Machine) -> Result<(), VMError>;
+
+//记录最近取过的指令条数, 用于发现自修改代码
+pub const RECENT_FETCHES: usize = 8;
+
+//客户程序写入了最近取过的指令, 参数是写入的地址
+//JIT和bootloader会这样做, 设置后方便调试
+pub type SmcHook = fn(&mut Machine, u32);
+
+impl Machine {
+    //address的最低位会被忽略, Thumb函数的地址可以直接传进来
+    pub fn hook_function(&mut self, address: u32, hook: FunctionHook) -> Result<(), VMError> {
+        let address = address & !1;
+        if let Some(slot) = self
+            .function_hooks
+            .iter_mut()
+            .find(|slot| matches!(slot, Some((a, _)) if *a == address))
+        {
+            *slot = Some((address, hook));
+            return Ok(());
+        }
+        let slot = self
+            .function_hooks
+            .iter_mut()
+            .find(|slot| slot.is_none())
+            .ok_or(VMError::HookTableFull)?;
+        *slot = Some((address, hook));
+        Ok(())
+    }
+
+    pub fn unhook_function(&mut self, address: u32) {
+        let address = address & !1;
+        for slot in self.function_hooks.iter_mut() {
+            if matches!(slot, Some((a, _)) if *a == address) {
+                *slot = None;
+            }
+        }
+    }
+
+    //step在取指令前调用, PC到了钩住的函数时调用钩子并返回到LR
+    pub fn run_function_hook(&mut self, address: u32) -> Result<bool, VMError> {
+        let hook = self.function_hooks.iter().find_map(|slot| match slot {
+            Some((a, hook)) if *a == address => Some(*hook),
+            _ => None,
+        });
+        let Some(hook) = hook else {
+            return Ok(false);
+        };
+        hook(self)?;
+        self.bw_write_pc(self.cpu.regs[LR_INDEX])?;
+        Ok(true)
+    }
+
+    //step取完指令后调用, 只在设置了smc_hook时记录
+    pub fn record_recent_fetch(&mut self, len: u32) {
+        if self.smc_hook.is_none() {
+            return;
+        }
+        self.recent_fetches.rotate_right(1);
+        self.recent_fetches[0] = Some((self.mark, len));
+    }
+
+    //write_memory写入前调用, 写到了最近取过的指令时调用smc_hook
+    //命中的记录随之作废, 一次写入一个字只报告一次
+    pub fn check_self_modifying(&mut self, address: u32) {
+        let Some(smc_hook) = self.smc_hook else {
+            return;
+        };
+        let mut hit = false;
+        for slot in self.recent_fetches.iter_mut() {
+            if matches!(slot, Some((start, len)) if address.wrapping_sub(*start) < *len) {
+                *slot = None;
+                hit = true;
+            }
+        }
+        if hit {
+            smc_hook(self, address);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::machine::tests::arm_machine;
+
+    #[test]
+    fn function_hook_sets_r0_and_returns_to_lr() {
+        let mut machine = arm_machine(&[
+            0xe3a00001, //mov r0, #1
+            0xeb00003d, //bl 0x100
+            0xe1a01000, //mov r1, r0
+        ]);
+        machine.memory.data[0x100..0x104].copy_from_slice(&0xe3a00063u32.to_le_bytes()); //mov r0, #99
+        machine
+            .hook_function(0x100, |machine| {
+                machine.cpu.regs[0] = 42;
+                Ok(())
+            })
+            .unwrap();
+        for _ in 0..4 {
+            machine.step().unwrap();
+        }
+        assert_eq!(machine.cpu.regs[1], 42);
+        assert_eq!(machine.cpu.regs[15], 12);
+    }
+
+    #[test]
+    fn smc_hook_fires_on_write_to_fetched_instruction() {
+        let mut machine = arm_machine(&[
+            0xe3a00004, //mov r0, #4
+            0xe3a02c01, //mov r2, #0x100
+            0xe5821000, //str r1, [r2]
+            0xe5801000, //str r1, [r0]
+        ]);
+        //r7记录调用次数, r8记录写入的地址
+        machine.smc_hook = Some(|machine, address| {
+            machine.cpu.regs[7] += 1;
+            machine.cpu.regs[8] = address;
+        });
+        machine.cpu.regs[1] = 0xe1a00000; //mov r0, r0
+        for _ in 0..3 {
+            machine.step().unwrap();
+        }
+        assert_eq!(machine.cpu.regs[7], 0);
+        //一次写入一个字只报告一次
+        machine.step().unwrap();
+        assert_eq!(machine.cpu.regs[7], 1);
+        assert_eq!(machine.cpu.regs[8], 4);
+    }
+}
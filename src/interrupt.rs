@@ -0,0 +1,169 @@
+use crate::{
+    cpu::{InstrSet, LR_INDEX, PC_INDEX},
+    machine::{Endianness, Machine},
+    vmerror::VMError,
+};
+
+//处理器模式, CPSR.M
+pub const MODE_FIQ: u32 = 0b10001;
+pub const MODE_IRQ: u32 = 0b10010;
+pub const MODE_SVC: u32 = 0b10011;
+pub const MODE_ABORT: u32 = 0b10111;
+pub const MODE_UNDEFINED: u32 = 0b11011;
+
+//异常向量表的起始地址, 相当于A-profile的SCTLR.V为0, 没有VBAR
+pub const VECTOR_BASE: u32 = 0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExceptionKind {
+    Reset,
+    UndefinedInstruction,
+    SupervisorCall,
+    //取指令出错
+    PrefetchAbort,
+    //读写数据出错
+    DataAbort,
+    Irq,
+    Fiq,
+}
+
+impl ExceptionKind {
+    //在向量表中的偏移
+    pub fn vector_offset(self) -> u32 {
+        match self {
+            ExceptionKind::Reset => 0x00,
+            ExceptionKind::UndefinedInstruction => 0x04,
+            ExceptionKind::SupervisorCall => 0x08,
+            ExceptionKind::PrefetchAbort => 0x0c,
+            ExceptionKind::DataAbort => 0x10,
+            ExceptionKind::Irq => 0x18,
+            ExceptionKind::Fiq => 0x1c,
+        }
+    }
+
+    //进入的模式
+    pub fn mode(self) -> u32 {
+        match self {
+            ExceptionKind::Reset | ExceptionKind::SupervisorCall => MODE_SVC,
+            ExceptionKind::UndefinedInstruction => MODE_UNDEFINED,
+            ExceptionKind::PrefetchAbort | ExceptionKind::DataAbort => MODE_ABORT,
+            ExceptionKind::Irq => MODE_IRQ,
+            ExceptionKind::Fiq => MODE_FIQ,
+        }
+    }
+
+    //执行出错时对应的异常, 串口通信这类模拟器自己的错误没有对应的异常
+    //Unimplemented是模拟器还没有实现这条指令, 不是客户程序的错, 也不进入异常
+    pub fn from_error(error: VMError) -> Option<ExceptionKind> {
+        match error {
+            VMError::BusError | VMError::AlignmentFault => Some(ExceptionKind::DataAbort),
+            VMError::UndefinedInstruction | VMError::Unpredictable | VMError::DecodeError(_) => {
+                Some(ExceptionKind::UndefinedInstruction)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Machine {
+    //step在取指令前调用, 有挂起的软件中断且CPSR.I为0时进入处理程序
+    //触发它的指令已经执行完了, 所以处理程序总是在下一条指令之前运行
+    pub fn check_software_interrupt(&mut self) {
+        if self.software_interrupt_pending && !self.cpu.cpsr.i() {
+            self.software_interrupt_pending = false;
+            self.take_software_interrupt();
+        }
+    }
+
+    //按IRQ异常进入software_interrupt_handler, 处理程序用SUBS PC, LR, #4返回
+    fn take_software_interrupt(&mut self) {
+        //下一条要执行的指令 + 4
+        let return_address = self.cpu.regs[PC_INDEX].wrapping_add(4);
+        self.enter_exception(ExceptionKind::Irq, return_address);
+        let handler = self.software_interrupt_handler;
+        if handler & 1 == 1 {
+            self.select_instr_set(InstrSet::Thumb);
+        } else {
+            self.select_instr_set(InstrSet::Arm);
+        }
+        self.branch_to(handler & !1);
+    }
+
+    //保存CPSR到新模式的SPSR, 设置LR, 屏蔽中断, 清除IT块, 按默认字节序设置CPSR.E
+    //还没有分模式的寄存器组, 进入时原来的LR会被覆盖
+    fn enter_exception(&mut self, kind: ExceptionKind, return_address: u32) {
+        let cpsr = self.cpu.cpsr;
+        self.cpu.cpsr.set_m(kind.mode());
+        *self.cpu.spsr_mut() = cpsr;
+        self.cpu.regs[LR_INDEX] = return_address;
+        self.cpu.cpsr.set_i(true);
+        if kind != ExceptionKind::UndefinedInstruction && kind != ExceptionKind::SupervisorCall {
+            self.cpu.cpsr.set_a(true);
+        }
+        if kind == ExceptionKind::Reset || kind == ExceptionKind::Fiq {
+            self.cpu.cpsr.set_f(true);
+        }
+        self.cpu.cpsr.set_it(0);
+        self.cpu
+            .cpsr
+            .set_e(self.default_endianness == Endianness::Big);
+    }
+
+    //进入异常, 跳到向量表中对应的地址, 总是在Arm状态下执行(SCTLR.TE为0)
+    //mark是出错的指令的地址, PC已经指向下一条指令, LR按各个异常的返回方式设置:
+    //未定义指令和SVC用MOVS PC, LR返回到下一条指令
+    //PrefetchAbort用SUBS PC, LR, #4, DataAbort用SUBS PC, LR, #8重新执行出错的指令
+    //IRQ和FIQ用SUBS PC, LR, #4返回到下一条指令
+    pub fn take_exception(&mut self, kind: ExceptionKind) {
+        let return_address = match kind {
+            //复位时LR是UNKNOWN, 保持不变
+            ExceptionKind::Reset => self.cpu.regs[LR_INDEX],
+            ExceptionKind::UndefinedInstruction | ExceptionKind::SupervisorCall => {
+                self.cpu.regs[PC_INDEX]
+            }
+            ExceptionKind::PrefetchAbort => self.mark.wrapping_add(4),
+            ExceptionKind::DataAbort => self.mark.wrapping_add(8),
+            ExceptionKind::Irq | ExceptionKind::Fiq => self.cpu.regs[PC_INDEX].wrapping_add(4),
+        };
+        self.enter_exception(kind, return_address);
+        self.select_instr_set(InstrSet::Arm);
+        self.branch_to(VECTOR_BASE + kind.vector_offset());
+    }
+
+    //SUBS PC, LR, #imm和MOVS PC, LR这类指令从异常返回, 用SPSR恢复CPSR
+    //同时清除独占监视器, 被打断的LDREX/STREX会失败重试
+    pub fn exception_return(&mut self, address: u32) {
+        self.cpu.cpsr = *self.cpu.spsr();
+        self.clear_exclusive_local();
+        self.branch_write_pc(address);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::machine::{ErrorAction, HaltReason, tests::arm_machine};
+
+    #[test]
+    fn bus_error_enters_data_abort_vector() {
+        let mut machine = arm_machine(&[
+            0xe3a00203, //mov r0, #0x30000000
+            0xe5901000, //ldr r1, [r0]
+        ]);
+        machine.on_error = Some(|_, _| ErrorAction::VectorToGuest);
+        assert!(matches!(machine.run_bounded(2), HaltReason::StepLimit));
+        assert_eq!(machine.cpu.regs[PC_INDEX], VECTOR_BASE + 0x10);
+        assert_eq!(machine.cpu.cpsr.m(), MODE_ABORT);
+        //SUBS PC, LR, #8返回到出错的指令
+        assert_eq!(machine.cpu.regs[LR_INDEX], 4 + 8);
+    }
+
+    #[test]
+    fn unimplemented_has_no_exception() {
+        assert_eq!(ExceptionKind::from_error(VMError::Unimplemented), None);
+        assert_eq!(
+            ExceptionKind::from_error(VMError::AlignmentFault),
+            Some(ExceptionKind::DataAbort)
+        );
+    }
+}
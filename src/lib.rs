@@ -0,0 +1,26 @@
+#![cfg_attr(not(test), no_std)]
+
+//解释器核心, 不依赖具体的板子, 可以在电脑上编译
+extern crate alloc;
+
+pub mod arithmetic;
+pub mod breakpoint;
+pub mod cache;
+pub mod coverage;
+pub mod cpu;
+pub mod disassemble;
+pub mod exclusive;
+pub mod executor;
+pub mod hook;
+pub mod interrupt;
+pub mod machine;
+pub mod memory;
+pub mod mmio;
+pub mod protocol;
+pub mod replay;
+pub mod semihosting;
+pub mod serial;
+pub mod symbols;
+pub mod unimplemented;
+pub mod uninit;
+pub mod vmerror;
@@ -1,19 +1,137 @@
+use alloc::boxed::Box;
+use alloc::vec::Vec;
 use bitvec::field::BitField;
 use bitvec::order::Lsb0;
 use bitvec::view::BitView;
-use yaxpeax_arch::{Decoder, ReadError, Reader};
-use yaxpeax_arm::armv7::{ConditionCode, InstDecoder, Operand, RegShiftStyle};
+use core::cell::RefCell;
+use yaxpeax_arch::{Decoder, LengthedInstruction, ReadError, Reader};
+use yaxpeax_arm::armv7::{
+    ConditionCode, InstDecoder, Operand, RegShift, RegShiftStyle, ShiftStyle,
+};
 
 use crate::arithmetic::*;
+use crate::breakpoint::MAX_BREAKPOINTS;
+use crate::cache::ExternalCache;
 use crate::cpu::{CPU, InstrSet, PC_INDEX};
+use crate::hook::{FunctionHook, MAX_FUNCTION_HOOKS, RECENT_FETCHES, SmcHook};
+use crate::interrupt::ExceptionKind;
 use crate::memory::Memory;
+use crate::mmio::{MAX_MMIO_HANDLERS, MmioHandler};
+use crate::protocol::Command;
+use crate::replay::ReplayLog;
+use crate::semihosting::SvcHandler;
+use crate::serial::Serial;
+use crate::symbols::Symbol;
+use crate::unimplemented::{OPCODE_CATEGORIES, UnimplementedAction};
+use crate::uninit::UninitReadHook;
 use crate::vmerror::VMError;
 
+#[derive(Debug)]
+pub enum HaltReason {
+    //run_bounded执行完了指定数量的指令
+    StepLimit,
+    //客户程序写了EXIT寄存器, 值为写入的退出码
+    GuestExit(u32),
+    //执行到了断点, 值为断点的地址, 这条指令还没有执行
+    BreakpointHit(u32),
+    //host_poll要求暂停
+    Paused,
+    //设置了halt_on_wfi时执行了WFI, 或者执行了要等待事件的WFE, 这条指令已经执行完
+    WaitForInterrupt,
+    Error(VMError),
+}
+
+//执行出错时怎么处理, 由on_error返回
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ErrorAction {
+    //忽略错误, 从下一条指令继续执行
+    Continue,
+    //停止运行, run返回HaltReason::Error
+    Halt,
+    //进入对应的异常, 交给客户程序的异常处理, 见ExceptionKind::from_error
+    VectorToGuest,
+}
+
+//host_poll的返回值
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PollAction {
+    Continue,
+    //暂停运行, run返回HaltReason::Paused
+    Pause,
+}
+
+//复位后数据访问的字节序, 决定CPSR.E的初始值
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Endianness {
+    Little,
+    Big,
+}
+
 pub struct Machine {
     pub cpu: CPU,
     pub arch_version: u32,
+    pub default_endianness: Endianness,
     pub memory: Memory,
+    //register_mmio注册的MMIO区域
+    pub mmio_handlers: [Option<MmioHandler>; MAX_MMIO_HANDLERS],
     pub mark: u32,
+    //正在执行的指令的编码, 取指令时由Reader::next记下, 前offset个字节有效, 总是小端
+    pub encoding: [u8; 4],
+    pub halt_reason: Option<HaltReason>,
+    //周期计数, 每条指令1个周期, 跳转另加branch_penalty
+    pub cycles: u32,
+    //跳转后重新填充流水线的额外周期数
+    pub branch_penalty: u32,
+    //POP时要求SP按ABI4字节对齐, 默认不检查
+    pub check_stack_alignment: bool,
+    //相当于SCTLR.A, 字和半字的LDR, STR也要求对齐, 默认不检查
+    pub strict_alignment: bool,
+    //检查指令中应为0或者应为1的位, 见check_reserved_bits, 默认不检查
+    pub strict: bool,
+    //用于访问外部内存, read_memory只有&self, 所以放在RefCell里
+    pub serial: RefCell<Option<Box<dyn Serial>>>,
+    //外部内存的缓存, 为None时每次访问都通过串口
+    pub external_cache: RefCell<Option<ExternalCache>>,
+    //调用start_recording后记录每条取到的指令
+    pub recording: Option<ReplayLog>,
+    //调用enable_coverage后记录执行过的地址, 见coverage.rs
+    pub coverage: Option<Vec<u8>>,
+    pub breakpoints: [Option<u32>; MAX_BREAKPOINTS],
+    //刚停在这个断点上, 继续运行时先执行这条指令
+    pub resume_from_breakpoint: Option<u32>,
+    //钩住的函数地址和对应的钩子
+    pub function_hooks: [Option<(u32, FunctionHook)>; MAX_FUNCTION_HOOKS],
+    //写入了最近取过的指令时调用, 没有设置时不检查
+    pub smc_hook: Option<SmcHook>,
+    //最近取过的指令的(地址, 字节数), 最新的在前面
+    pub recent_fetches: [Option<(u32, u32)>; RECENT_FETCHES],
+    //每类还没有实现的指令遇到时怎么处理, 按OpcodeCategory的顺序
+    pub unimplemented_policy: [UnimplementedAction; OPCODE_CATEGORIES],
+    //执行出错时调用, 没有设置时停止运行
+    pub on_error: Option<fn(&mut Machine, VMError) -> ErrorAction>,
+    //每执行poll_interval条指令调用一次, 让电脑有机会暂停或读写状态
+    pub host_poll: Option<fn(&mut Machine) -> PollAction>,
+    pub poll_interval: u32,
+    //距离下次调用host_poll还剩的指令数
+    pub poll_countdown: u32,
+    //客户程序写SOFTWARE_INTERRUPT_REGISTER后挂起, 在下一条指令之前进入处理程序, 见interrupt.rs
+    pub software_interrupt_pending: bool,
+    //软件中断处理程序的地址, 最低位为1时是Thumb
+    pub software_interrupt_handler: u32,
+    //add_symbol注册的符号, 按地址排序
+    pub symbols: Vec<Symbol>,
+    //事件寄存器, SEV置位, WFE清除
+    pub event_register: bool,
+    //WFI和要等待的WFE让run停下, 返回HaltReason::WaitForInterrupt, 默认当作NOP继续执行
+    pub halt_on_wfi: bool,
+    //调用enable_uninit_detection后记录写过的内部内存, 见uninit.rs
+    pub initialized: Option<Vec<u8>>,
+    //读到没有写过的内存时调用, 没有设置时报错
+    pub uninit_read_hook: Option<UninitReadHook>,
+    //本地独占监视器, LDREX记录的(地址, 字节数), 见exclusive.rs
+    pub exclusive_monitor: Option<(u32, u32)>,
+    //执行SVC时调用, 没有设置时SVC什么也不做, 见semihosting.rs
+    pub svc_handler: Option<SvcHandler>,
 }
 
 impl Default for Machine {
@@ -21,8 +139,40 @@ impl Default for Machine {
         let cpu = CPU::default();
         Machine {
             arch_version: 7,
+            default_endianness: Endianness::Little,
             memory: Memory::default(),
+            mmio_handlers: [None; MAX_MMIO_HANDLERS],
             mark: cpu.regs[PC_INDEX],
+            encoding: [0; 4],
+            halt_reason: None,
+            cycles: 0,
+            branch_penalty: 2,
+            check_stack_alignment: false,
+            strict_alignment: false,
+            strict: false,
+            serial: RefCell::new(None),
+            external_cache: RefCell::new(None),
+            recording: None,
+            coverage: None,
+            breakpoints: [None; MAX_BREAKPOINTS],
+            resume_from_breakpoint: None,
+            function_hooks: [None; MAX_FUNCTION_HOOKS],
+            smc_hook: None,
+            recent_fetches: [None; RECENT_FETCHES],
+            unimplemented_policy: [UnimplementedAction::Trap; OPCODE_CATEGORIES],
+            on_error: None,
+            host_poll: None,
+            poll_interval: 1024,
+            poll_countdown: 1024,
+            software_interrupt_pending: false,
+            software_interrupt_handler: 0,
+            symbols: Vec::new(),
+            event_register: false,
+            halt_on_wfi: false,
+            initialized: None,
+            uninit_read_hook: None,
+            exclusive_monitor: None,
+            svc_handler: None,
             cpu,
         }
     }
@@ -34,8 +184,16 @@ impl Reader<u32, u8> for Machine {
         if (address as usize) >= self.memory.size() {
             return Err(ReadError::ExhaustedInput);
         }
-        self.cpu.regs[PC_INDEX] += 1;
-        Ok(self.read_memory(address)?)
+        //读成功了才移动PC
+        let byte = self.read_memory(address)?;
+        if let Some(slot) = self
+            .encoding
+            .get_mut(address.wrapping_sub(self.mark) as usize)
+        {
+            *slot = byte;
+        }
+        self.cpu.regs[PC_INDEX] = address.wrapping_add(1);
+        Ok(byte)
     }
 
     fn next_n(&mut self, buf: &mut [u8]) -> Result<(), ReadError> {
@@ -53,7 +211,7 @@ impl Reader<u32, u8> for Machine {
     }
 
     fn offset(&mut self) -> u32 {
-        self.cpu.regs[PC_INDEX] - self.mark
+        self.cpu.regs[PC_INDEX].wrapping_sub(self.mark)
     }
 
     fn total_offset(&mut self) -> u32 {
@@ -62,6 +220,66 @@ impl Reader<u32, u8> for Machine {
 }
 
 impl Machine {
+    //arch_version目前只区分4, 5, 6, 7
+    pub fn with_arch_version(arch_version: u32) -> Machine {
+        Machine {
+            arch_version,
+            ..Default::default()
+        }
+    }
+
+    //不用先执行SETEND BE就能运行大端的程序
+    pub fn with_endianness(endianness: Endianness) -> Machine {
+        let mut machine = Machine {
+            default_endianness: endianness,
+            ..Default::default()
+        };
+        machine.cpu.cpsr.set_e(endianness == Endianness::Big);
+        machine
+    }
+
+    pub fn attach_serial(&mut self, serial: Box<dyn Serial>) {
+        self.serial = RefCell::new(Some(serial));
+    }
+
+    //回到上电时的执行状态: 寄存器, CPSR(包括ITSTATE), 周期计数和停下的原因
+    //内存, 断点, 钩子和各种设置都保留
+    pub fn reset_execution_state(&mut self) {
+        self.cpu = CPU::default();
+        self.cpu
+            .cpsr
+            .set_e(self.default_endianness == Endianness::Big);
+        self.mark = self.cpu.regs[PC_INDEX];
+        self.halt_reason = None;
+        self.resume_from_breakpoint = None;
+        self.recent_fetches = [None; RECENT_FETCHES];
+        self.cycles = 0;
+        self.poll_countdown = self.poll_interval;
+        self.event_register = false;
+        self.software_interrupt_pending = false;
+        self.exclusive_monitor = None;
+    }
+
+    //在reset_execution_state的基础上把内部内存清零, 开启了未初始化读检测时记录也清空
+    pub fn reset(&mut self) {
+        self.reset_execution_state();
+        self.memory.data.fill(0);
+        if let Some(initialized) = &mut self.initialized {
+            initialized.fill(0);
+        }
+    }
+
+    //设置复位后开始执行的地址, 最低位为1时从Thumb状态开始
+    pub fn set_entry_point(&mut self, entry: u32) {
+        if entry & 1 == 1 {
+            self.select_instr_set(InstrSet::Thumb);
+        } else {
+            self.select_instr_set(InstrSet::Arm);
+        }
+        self.cpu.regs[PC_INDEX] = entry & !1;
+        self.mark = self.cpu.regs[PC_INDEX];
+    }
+
     /* P2639
     IsZero(x) = (BitCount(x) == 0)
     IsOnes(x) = (BitCount(x) == Len(x))
@@ -70,7 +288,12 @@ impl Machine {
     */
     //P287
     pub fn condition_passed(&self, cond: ConditionCode) -> bool {
-        let cond = cond as u8;
+        //P288 CurrentCond: IT块中的指令由ITSTATE给出条件
+        let cond = if self.in_it_block() {
+            self.cpu.it_state().0 >> 4
+        } else {
+            cond as u8
+        };
         let apsr = self.cpu.apsr();
         let mut result = match cond >> 1 & 0b111 {
             0b000 => apsr.z(),                                  // EQ or NE
@@ -90,14 +313,15 @@ impl Machine {
     }
 
     //P48
-    pub fn alu_write_pc(&mut self, address: u32) {
+    pub fn alu_write_pc(&mut self, address: u32) -> Result<(), VMError> {
         if self.arch_version >= 7
             && let InstrSet::Arm = self.current_instr_set()
         {
-            self.bw_write_pc(address);
+            self.bw_write_pc(address)?;
         } else {
             self.branch_write_pc(address);
         }
+        Ok(())
     }
 
     //P47
@@ -112,9 +336,10 @@ impl Machine {
 
     //P47
     //跳转但可以切换指令集
-    pub fn bw_write_pc(&mut self, address: u32) {
+    pub fn bw_write_pc(&mut self, address: u32) -> Result<(), VMError> {
         match self.current_instr_set() {
-            InstrSet::ThumbEE => unimplemented!(),
+            //只支持Arm和Thumb
+            InstrSet::ThumbEE => return Err(VMError::Unimplemented),
             _ => {
                 if address & 1 == 1 {
                     self.select_instr_set(InstrSet::Thumb);
@@ -122,81 +347,206 @@ impl Machine {
                 } else if address >> 1 & 1 == 0 {
                     self.select_instr_set(InstrSet::Arm);
                     self.branch_to(address);
+                } else {
+                    //最低两位是0b10时是UNPREDICTABLE, 不跳转, 报错
+                    return Err(VMError::Unpredictable);
                 }
             }
         }
+        Ok(())
     }
 
     //P47
-    pub fn load_write_pc(&mut self, address: u32) {
+    pub fn load_write_pc(&mut self, address: u32) -> Result<(), VMError> {
         if self.arch_version >= 5 {
-            self.bw_write_pc(address);
+            self.bw_write_pc(address)?;
         } else {
             self.branch_write_pc(address);
         }
+        Ok(())
+    }
+
+    //跳转指令的目标地址
+    //解码器给出的偏移已经加上了读PC时的偏移(Arm为2个字, Thumb为1或2个半字), 是相对这条指令开头的
+    //这里没有流水线, 执行时PC已经指向下一条指令, 所以要先减去一个单位
+    pub fn branch_target(&self, operand: Operand) -> Result<u32, VMError> {
+        self.branch_target_from(self.cpu.regs[PC_INDEX], operand)
+    }
+
+    //同branch_target, pc是这条指令之后的地址, 反汇编时不能用regs[PC_INDEX]
+    pub fn branch_target_from(&self, pc: u32, operand: Operand) -> Result<u32, VMError> {
+        let base = match operand {
+            Operand::BranchOffset(..) => pc.wrapping_sub(4),
+            Operand::BranchThumbOffset(..) => pc.wrapping_sub(2),
+            _ => unreachable!(),
+        };
+        Ok(base.wrapping_add(self.read(operand)?))
     }
 
     //1147
     pub fn branch_to(&mut self, address: u32) {
         self.cpu.regs[PC_INDEX] = address;
+        self.cycles = self.cycles.wrapping_add(self.branch_penalty);
     }
 
     // P2641
     pub fn align(&self, address: u32, alignment: u32) -> u32 {
-        (address + alignment - 1) & !(alignment - 1)
+        address & !(alignment - 1)
+    }
+
+    //指令读到的PC: Arm为这条指令的地址+8, Thumb为+4
+    //执行时regs[PC_INDEX]已经指向下一条指令, 所以从mark算出
+    pub fn pc_value(&self) -> u32 {
+        match self.current_instr_set() {
+            InstrSet::Arm => self.mark.wrapping_add(8),
+            _ => self.mark.wrapping_add(4),
+        }
+    }
+
+    //读作操作数的寄存器, PC见pc_value
+    pub fn reg_value(&self, index: usize) -> u32 {
+        if index == PC_INDEX {
+            self.pc_value()
+        } else {
+            self.cpu.regs[index]
+        }
+    }
+
+    //用作基址的寄存器, PC(比如LDR literal)要先按4字节对齐
+    fn base_value(&self, index: usize) -> u32 {
+        if index == PC_INDEX {
+            self.align(self.pc_value(), 4)
+        } else {
+            self.cpu.regs[index]
+        }
     }
 
     pub fn read_address(&self, operand: Operand) -> Result<u32, VMError> {
         Ok(match operand {
-            Operand::RegDeref(reg) => self.cpu.regs[reg.number() as usize],
-            Operand::RegDerefPostindexOffset(reg, ..) => self.cpu.regs[reg.number() as usize],
-            Operand::RegDerefPostindexReg(reg, ..) => self.cpu.regs[reg.number() as usize],
-            Operand::RegDerefPostindexRegShift(reg, ..) => self.cpu.regs[reg.number() as usize],
+            Operand::RegDeref(reg) => self.base_value(reg.number() as usize),
+            Operand::RegDerefPostindexOffset(reg, ..) => self.base_value(reg.number() as usize),
+            Operand::RegDerefPostindexReg(reg, ..) => self.base_value(reg.number() as usize),
+            Operand::RegDerefPostindexRegShift(reg, ..) => self.base_value(reg.number() as usize),
             Operand::RegDerefPreindexOffset(reg, offset, add, ..) => {
-                let a = self.cpu.regs[reg.number() as usize];
+                let a = self.base_value(reg.number() as usize);
                 let b = offset as u32;
-                if add { a + b } else { a - b }
+                if add {
+                    a.wrapping_add(b)
+                } else {
+                    a.wrapping_sub(b)
+                }
             }
             Operand::RegDerefPreindexReg(reg, reg2, add, ..) => {
-                let a = self.cpu.regs[reg.number() as usize];
+                let a = self.base_value(reg.number() as usize);
                 let b = self.cpu.regs[reg2.number() as usize];
-                if add { a + b } else { a - b }
+                if add {
+                    a.wrapping_add(b)
+                } else {
+                    a.wrapping_sub(b)
+                }
             }
             Operand::RegDerefPreindexRegShift(reg, reg_shift, add, ..) => {
-                let a = self.cpu.regs[reg.number() as usize];
+                let a = self.base_value(reg.number() as usize);
                 let b = self.read(Operand::RegShift(reg_shift))?;
-                if add { a + b } else { a - b }
+                if add {
+                    a.wrapping_add(b)
+                } else {
+                    a.wrapping_sub(b)
+                }
             }
             _ => unreachable!(),
         })
     }
 
+    //P291 P292
+    pub fn read_reg_shift_with_carry(&self, reg_shift: RegShift) -> (u32, bool) {
+        let carry_in = self.cpu.apsr().c();
+        match reg_shift.into_shift() {
+            RegShiftStyle::RegImm(reg_imm_shift) => {
+                let shift_style = reg_imm_shift.stype();
+                let value = self.reg_value(reg_imm_shift.shiftee().number() as usize);
+                // DecodeImmShift: LSR #0和ASR #0表示移32位, ROR #0表示RRX
+                let amount = match (shift_style, reg_imm_shift.imm()) {
+                    (ShiftStyle::LSR | ShiftStyle::ASR, 0) => 32,
+                    (_, imm) => imm as u32,
+                };
+                shift_c(value, shift_style, amount, carry_in)
+            }
+            RegShiftStyle::RegReg(reg_reg_shift) => {
+                let value = self.reg_value(reg_reg_shift.shiftee().number() as usize);
+                //移位量只取Rs的低8位
+                let amount = self.cpu.regs[reg_reg_shift.shifter().number() as usize] & 0xff;
+                if amount == 0 {
+                    //寄存器移位量为0时ROR不是RRX, 值和carry都不变
+                    (value, carry_in)
+                } else {
+                    shift_c(value, reg_reg_shift.stype(), amount, carry_in)
+                }
+            }
+        }
+    }
+
+    //Arm指令的编码
+    pub fn arm_encoding(&self) -> u32 {
+        u32::from_le_bytes(self.encoding)
+    }
+
+    //32位Thumb指令的两个半字
+    pub fn thumb_encoding(&self) -> (u32, u32) {
+        let [b0, b1, b2, b3] = self.encoding;
+        (
+            u16::from_le_bytes([b0, b1]) as u32,
+            u16::from_le_bytes([b2, b3]) as u32,
+        )
+    }
+
+    //解码器已经把修饰立即数展开成了Imm32, 丢掉了展开时的carry, 只能从这条指令的编码重新展开
+    //不是修饰立即数时(比如16位Thumb的MOVS Rd, #imm8), carry保持不变
+    pub fn immediate_carry(&self, len: u32) -> bool {
+        let carry_in = self.cpu.apsr().c();
+        match (self.current_instr_set(), len) {
+            (InstrSet::Arm, 4) => {
+                let word = self.arm_encoding();
+                //数据处理(立即数)
+                if word >> 25 & 0b111 == 0b001 {
+                    arm_expand_imm_c(word & 0xfff, carry_in).1
+                } else {
+                    carry_in
+                }
+            }
+            (InstrSet::Thumb, 4) => {
+                let (hw1, hw2) = self.thumb_encoding();
+                //数据处理(修饰立即数)
+                if hw1 & 0xfa00 == 0xf000 && hw2 >> 15 == 0 {
+                    let imm12 = (hw1 >> 10 & 1) << 11 | (hw2 >> 12 & 0b111) << 8 | hw2 & 0xff;
+                    thumb_expand_imm_c(imm12, carry_in).1
+                } else {
+                    carry_in
+                }
+            }
+            _ => carry_in,
+        }
+    }
+
+    //32位Thumb的MOVW和MOVT的imm16, 不是这两条指令时返回None
+    //解码器拼imm16时把imm4放在了bit16开始的位置, 只能从编码重新拼出imm4:i:imm3:imm8
+    pub fn thumb_imm16(&self, len: u32) -> Option<u32> {
+        if self.current_instr_set() != InstrSet::Thumb || len != 4 {
+            return None;
+        }
+        let (hw1, hw2) = self.thumb_encoding();
+        //11110 i 10 x 100 imm4, x为0时是MOVW, 为1时是MOVT
+        if hw1 & 0xfb70 != 0xf240 || hw2 >> 15 != 0 {
+            return None;
+        }
+        Some((hw1 & 0xf) << 12 | (hw1 >> 10 & 1) << 11 | (hw2 >> 12 & 0b111) << 8 | hw2 & 0xff)
+    }
+
     pub fn read_with_carry(&self, operand: Operand) -> Result<(u32, bool), VMError> {
         Ok(match operand {
-            Operand::RegShift(reg_shift) => {
-                let reg;
-                let shift_style;
-                let amount;
-                match reg_shift.into_shift() {
-                    RegShiftStyle::RegImm(reg_imm_shift) => {
-                        shift_style = reg_imm_shift.stype();
-                        amount = reg_imm_shift.imm() as u32;
-                        reg = reg_imm_shift.shiftee();
-                    }
-                    RegShiftStyle::RegReg(reg_reg_shift) => {
-                        shift_style = reg_reg_shift.stype();
-                        reg = reg_reg_shift.shiftee();
-                        amount = self.cpu.regs[reg_reg_shift.shifter().number() as usize];
-                    }
-                }
-                shift_c(
-                    self.cpu.regs[reg.number() as usize],
-                    shift_style,
-                    amount,
-                    self.cpu.apsr().c(),
-                )
-            }
-            _ => (self.read(operand)?, false),
+            Operand::RegShift(reg_shift) => self.read_reg_shift_with_carry(reg_shift),
+            //没有移位的寄存器不改变C, 立即数的C见immediate_carry
+            _ => (self.read(operand)?, self.cpu.apsr().c()),
         })
     }
 
@@ -204,33 +554,12 @@ impl Machine {
         Ok(match operand {
             Operand::Imm32(value) => value,
             Operand::Imm12(value) => value as u32,
-            Operand::Reg(reg) => self.cpu.regs[reg.number() as usize],
-            Operand::RegShift(reg_shift) => {
-                let reg;
-                let shift_style;
-                let amount;
-                match reg_shift.into_shift() {
-                    RegShiftStyle::RegImm(reg_imm_shift) => {
-                        shift_style = reg_imm_shift.stype();
-                        amount = reg_imm_shift.imm() as u32;
-                        reg = reg_imm_shift.shiftee();
-                    }
-                    RegShiftStyle::RegReg(reg_reg_shift) => {
-                        shift_style = reg_reg_shift.stype();
-                        reg = reg_reg_shift.shiftee();
-                        amount = self.cpu.regs[reg_reg_shift.shifter().number() as usize];
-                    }
-                }
-                shift(
-                    self.cpu.regs[reg.number() as usize],
-                    shift_style,
-                    amount,
-                    self.cpu.apsr().c(),
-                )
-            }
+            Operand::Reg(reg) => self.reg_value(reg.number() as usize),
+            Operand::RegShift(reg_shift) => self.read_reg_shift_with_carry(reg_shift).0,
             // u32 as i32和i32 as u32都只改变解释方式
-            Operand::BranchOffset(value) => ((value - 1) << 2) as u32,
-            Operand::BranchThumbOffset(value) => ((value - 1) << 1) as u32,
+            //跳转的目标地址见branch_target
+            Operand::BranchOffset(value) => (value << 2) as u32,
+            Operand::BranchThumbOffset(value) => (value << 1) as u32,
             Operand::RegWBack(reg, _wback) => self.cpu.regs[reg.number() as usize],
             Operand::RegList(registers) => registers as u32,
             Operand::RegDeref(..)
@@ -245,7 +574,8 @@ impl Machine {
             Operand::APSR => self.cpu.apsr().0,
             Operand::CPSR => self.cpu.cpsr.0,
             Operand::SPSR => self.cpu.spsr().0,
-            _ => unimplemented!(),
+            //banked register, 协处理器寄存器这类还不支持的操作数
+            _ => return Err(VMError::Unimplemented),
         })
     }
 
@@ -253,31 +583,32 @@ impl Machine {
         match operand {
             Operand::Reg(reg) => self.cpu.regs[reg.number() as usize] = value,
             Operand::RegWBack(reg, true) => self.cpu.regs[reg.number() as usize] = value,
-            Operand::RegDerefPostindexOffset(reg, offset, add, true) => {
+            //后变址总是写回, 解码器给出的wback为false
+            Operand::RegDerefPostindexOffset(reg, offset, add, _) => {
                 let reg = Operand::Reg(reg);
                 let b = offset as u32;
                 if add {
-                    self.write(reg, value + b)?;
+                    self.write(reg, value.wrapping_add(b))?;
                 } else {
-                    self.write(reg, value - b)?;
+                    self.write(reg, value.wrapping_sub(b))?;
                 }
             }
-            Operand::RegDerefPostindexReg(reg, reg2, add, true) => {
+            Operand::RegDerefPostindexReg(reg, reg2, add, _) => {
                 let reg = Operand::Reg(reg);
                 let b = self.cpu.regs[reg2.number() as usize];
                 if add {
-                    self.write(reg, value + b)?;
+                    self.write(reg, value.wrapping_add(b))?;
                 } else {
-                    self.write(reg, value - b)?;
+                    self.write(reg, value.wrapping_sub(b))?;
                 }
             }
-            Operand::RegDerefPostindexRegShift(reg, reg_shift, add, true) => {
+            Operand::RegDerefPostindexRegShift(reg, reg_shift, add, _) => {
                 let reg = Operand::Reg(reg);
                 let b = self.read(Operand::RegShift(reg_shift))?;
                 if add {
-                    self.write(reg, value + b)?;
+                    self.write(reg, value.wrapping_add(b))?;
                 } else {
-                    self.write(reg, value - b)?;
+                    self.write(reg, value.wrapping_sub(b))?;
                 }
             }
             Operand::RegDerefPreindexOffset(reg, .., true) => {
@@ -292,122 +623,493 @@ impl Machine {
                 let write_spsr = status_reg_mask >> 4 == 1;
                 let mask = status_reg_mask & 0xf;
                 if write_spsr {
-                    //P1153
-                    let spsr = self.cpu.spsr_mut().0.view_bits_mut::<Lsb0>();
-                    let value = value.view_bits::<Lsb0>();
-
-                    if mask >> 3 & 1 == 1 {
-                        // N,Z,C,V,Q flags, IT<1:0>,J execution state bits
-                        for i in 24..32 {
-                            spsr.set(i, value[i]);
-                        }
-                    }
+                    self.spsr_write_by_instr(value, mask);
+                } else {
+                    self.cpsr_write_by_instr(value, mask, false);
+                }
+            }
+            //直接写整个寄存器, 相当于mask为0b1111
+            Operand::CPSR => self.cpsr_write_by_instr(value, 0b1111, false),
+            Operand::SPSR => self.spsr_write_by_instr(value, 0b1111),
+            _ => {}
+        }
+        Ok(())
+    }
 
-                    if mask >> 2 & 1 == 1 {
-                        // GE<3:0> flags
-                        for i in 16..20 {
-                            spsr.set(i, value[i]);
-                        }
-                    }
+    //P1153
+    pub fn spsr_write_by_instr(&mut self, value: u32, mask: u32) {
+        let spsr = self.cpu.spsr_mut().0.view_bits_mut::<Lsb0>();
+        let value = value.view_bits::<Lsb0>();
 
-                    if mask >> 1 & 1 == 1 {
-                        // IT<7:2> execution state bits, E bit, A interrupt mask
-                        for i in 8..16 {
-                            spsr.set(i, value[i]);
-                        }
-                    }
+        if mask >> 3 & 1 == 1 {
+            // N,Z,C,V,Q flags, IT<1:0>,J execution state bits
+            for i in 24..32 {
+                spsr.set(i, value[i]);
+            }
+        }
 
-                    if mask & 1 == 1 {
-                        // I,F interrupt masks, T execution state bit
-                        for i in 5..8 {
-                            spsr.set(i, value[i]);
-                        }
-                        for i in 0..5 {
-                            spsr.set(i, value[i]);
-                        }
-                    }
+        if mask >> 2 & 1 == 1 {
+            // GE<3:0> flags
+            for i in 16..20 {
+                spsr.set(i, value[i]);
+            }
+        }
 
-                    self.cpu.spsr_mut().0 = spsr.load();
-                } else {
-                    //TODO P1153
-                    let cpsr = self.cpu.cpsr.0.view_bits_mut::<Lsb0>();
-                    let value = value.view_bits::<Lsb0>();
-                    let is_excpt_return = false;
-                    let privileged = false;
-                    let nmfi = false;
-
-                    if mask >> 3 & 1 == 1 {
-                        // N,Z,C,V,Q flags
-                        for i in 27..32 {
-                            cpsr.set(i, value[i]);
-                        }
-                        if is_excpt_return {
-                            // IT<1:0>,J execution state bits
-                            for i in 24..27 {
-                                cpsr.set(i, value[i]);
-                            }
-                        }
-                    }
+        if mask >> 1 & 1 == 1 {
+            // IT<7:2> execution state bits, E bit, A interrupt mask
+            for i in 8..16 {
+                spsr.set(i, value[i]);
+            }
+        }
 
-                    if mask >> 2 & 1 == 1 {
-                        // GE<3:0> flags
-                        for i in 16..20 {
-                            cpsr.set(i, value[i]);
-                        }
-                    }
+        if mask & 1 == 1 {
+            // I,F interrupt masks, T execution state bit
+            for i in 5..8 {
+                spsr.set(i, value[i]);
+            }
+            for i in 0..5 {
+                spsr.set(i, value[i]);
+            }
+        }
 
-                    if mask >> 1 & 1 == 1 {
-                        if is_excpt_return {
-                            // IT<7:2> execution state bits
-                            for i in 10..16 {
-                                cpsr.set(i, value[i]);
-                            }
-                        }
-                        cpsr.set(9, value[9]); // E bit is user-writable
-                        //TODO (IsSecure() Il SCR.AW == '1' Il HaveVirtExt())
-                        if privileged && false {
-                            cpsr.set(8, value[8]); // A interrupt mask
-                        }
-                    }
+        self.cpu.spsr_mut().0 = spsr.load();
+    }
 
-                    if mask & 1 == 1 {
-                        if privileged {
-                            cpsr.set(7, value[7]); // I interrupt mask
-                        }
-                        //TODO IsSecure() Il SCR.FW == '1' Il HaveVirtExt())
-                        if privileged && (!nmfi || value[6] == false) && false {
-                            cpsr.set(6, value[6]); // F interrupt mask
-                        }
-                        if is_excpt_return {
-                            cpsr.set(5, value[5]); // T execution state bit
-                        }
-                        if privileged {
-                            // CPSR<4:0>, mode bits
-                            for i in 0..5 {
-                                cpsr.set(i, value[i]);
-                            }
-                        }
-                    }
+    //P1153
+    pub fn cpsr_write_by_instr(&mut self, value: u32, mask: u32, is_excpt_return: bool) {
+        let privileged = self.current_mode_is_not_user();
+        let nmfi = false;
+        let cpsr = self.cpu.cpsr.0.view_bits_mut::<Lsb0>();
+        let value = value.view_bits::<Lsb0>();
 
-                    self.cpu.cpsr.0 = cpsr.load();
+        if mask >> 3 & 1 == 1 {
+            // N,Z,C,V,Q flags
+            for i in 27..32 {
+                cpsr.set(i, value[i]);
+            }
+            if is_excpt_return {
+                // IT<1:0>,J execution state bits
+                for i in 24..27 {
+                    cpsr.set(i, value[i]);
                 }
             }
-            _ => {}
         }
+
+        if mask >> 2 & 1 == 1 {
+            // GE<3:0> flags
+            for i in 16..20 {
+                cpsr.set(i, value[i]);
+            }
+        }
+
+        if mask >> 1 & 1 == 1 {
+            if is_excpt_return {
+                // IT<7:2> execution state bits
+                for i in 10..16 {
+                    cpsr.set(i, value[i]);
+                }
+            }
+            cpsr.set(9, value[9]); // E bit is user-writable
+            //TODO (IsSecure() Il SCR.AW == '1' Il HaveVirtExt())
+            if privileged && false {
+                cpsr.set(8, value[8]); // A interrupt mask
+            }
+        }
+
+        if mask & 1 == 1 {
+            if privileged {
+                cpsr.set(7, value[7]); // I interrupt mask
+            }
+            //TODO IsSecure() Il SCR.FW == '1' Il HaveVirtExt())
+            if privileged && (!nmfi || value[6] == false) && false {
+                cpsr.set(6, value[6]); // F interrupt mask
+            }
+            if is_excpt_return {
+                cpsr.set(5, value[5]); // T execution state bit
+            }
+            if privileged {
+                // CPSR<4:0>, mode bits
+                for i in 0..5 {
+                    cpsr.set(i, value[i]);
+                }
+            }
+        }
+
+        self.cpu.cpsr.0 = cpsr.load();
+    }
+
+    //按当前的指令集和状态配置解码器
+    pub fn decoder(&self) -> InstDecoder {
+        //不同版本能解码的指令不同
+        let mut decoder = match self.arch_version {
+            0..=4 => InstDecoder::armv4(),
+            5 => InstDecoder::armv5(),
+            6 => InstDecoder::armv6(),
+            _ => InstDecoder::armv7(),
+        };
+        decoder.set_thumb_mode(InstrSet::Thumb == self.current_instr_set());
+        decoder.set_apsr_c(self.cpu.apsr().c());
+        decoder.set_in_it_block(self.in_it_block());
+        decoder
+    }
+
+    pub fn step(&mut self) -> Result<(), VMError> {
+        self.check_software_interrupt();
+        let pc = self.cpu.regs[PC_INDEX];
+        if self.check_breakpoint(pc) {
+            //接了串口就通知电脑
+            if let Some(serial) = self.serial.get_mut() {
+                Command::BreakpointHit(pc).send(serial.as_mut())?;
+            }
+            self.halt_reason = Some(HaltReason::BreakpointHit(pc));
+            return Ok(());
+        }
+        //钩子代替了整个函数, 只算1个周期
+        if self.run_function_hook(pc)? {
+            self.cycles = self.cycles.wrapping_add(1);
+            return Ok(());
+        }
+        let decoder = self.decoder();
+        //解码器不会调用mark, 要在指令开始处手动调用
+        self.mark();
+        let instruction = match decoder.decode(self) {
+            Ok(t) => t,
+            Err(error) => {
+                //可能只取到了半条指令(比如32位Thumb指令跨过了内存的末尾), PC退回这条指令的开头
+                self.cpu.regs[PC_INDEX] = self.mark;
+                return Err(VMError::DecodeError(error));
+            }
+        };
+        //此时offset正好是这条指令的字节数
+        debug_assert_eq!(self.offset(), instruction.len().to_const());
+        self.record_fetched(instruction.len().to_const());
+        self.record_recent_fetch(instruction.len().to_const());
+        self.record_coverage();
+        if self.strict {
+            self.check_reserved_bits(&instruction)?;
+        }
+        self.execute(instruction)?;
+        self.cycles = self.cycles.wrapping_add(1);
         Ok(())
     }
 
-    pub fn run(&mut self) -> ! {
+    //PC处解码失败的指令的字节数: Arm总是4字节, Thumb看第一个半字是不是32位指令的开头
+    //取指令不受CPSR.E影响, 总是按小端读取
+    pub fn undecodable_len(&self) -> u32 {
+        if self.current_instr_set() != InstrSet::Thumb {
+            return 4;
+        }
+        let mut bytes = [0; 2];
+        if self
+            .read_memory_n(self.cpu.regs[PC_INDEX], &mut bytes)
+            .is_err()
+        {
+            return 2;
+        }
+        match u16::from_le_bytes(bytes) >> 11 {
+            0b11101..=0b11111 => 4,
+            _ => 2,
+        }
+    }
+
+    //IT指令和它的IT块当作一步执行, 不在IT块中时只执行一条指令, 返回执行了多少条指令
+    //中途停下(比如碰到断点)时不再继续
+    pub fn step_it_block(&mut self) -> Result<u32, VMError> {
+        self.step()?;
+        let mut count = 1;
+        while self.in_it_block() && self.halt_reason.is_none() {
+            self.step()?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    //写回外部内存的脏缓存行, 再把串口里还没发完的数据发出去
+    //停下时由run_bounded调用, 保证电脑能看到客户程序最后的写入
+    pub fn flush(&mut self) -> Result<(), VMError> {
+        self.flush_external_cache()?;
+        if let Some(serial) = self.serial.get_mut() {
+            serial.flush()?;
+        }
+        Ok(())
+    }
+
+    //最多执行max_steps条指令, 因为其他原因停下时先flush
+    pub fn run_bounded(&mut self, max_steps: u32) -> HaltReason {
+        let reason = self.run_steps(max_steps);
+        if matches!(reason, HaltReason::StepLimit) {
+            return reason;
+        }
+        match self.flush() {
+            //已经出错了就报告原来的错误
+            Err(error) if !matches!(reason, HaltReason::Error(_)) => HaltReason::Error(error),
+            _ => reason,
+        }
+    }
+
+    fn run_steps(&mut self, max_steps: u32) -> HaltReason {
+        for _ in 0..max_steps {
+            if let Err(error) = self.step() {
+                let action = match self.on_error {
+                    Some(on_error) => on_error(self, error),
+                    None => ErrorAction::Halt,
+                };
+                //出错的指令已经取完了, PC指向下一条指令
+                //解码失败时PC还在这条指令的开头, 要跳过它, 否则会一直解码失败
+                if action != ErrorAction::Halt
+                    && let VMError::DecodeError(_) = error
+                {
+                    let len = self.undecodable_len();
+                    self.cpu.regs[PC_INDEX] = self.cpu.regs[PC_INDEX].wrapping_add(len);
+                }
+                match action {
+                    ErrorAction::Continue => {}
+                    ErrorAction::Halt => return HaltReason::Error(error),
+                    //没有对应的异常时和Halt一样
+                    ErrorAction::VectorToGuest => match ExceptionKind::from_error(error) {
+                        Some(kind) => self.take_exception(kind),
+                        None => return HaltReason::Error(error),
+                    },
+                }
+            }
+            if let Some(reason) = self.halt_reason.take() {
+                return reason;
+            }
+            if let Some(host_poll) = self.host_poll {
+                self.poll_countdown = self.poll_countdown.saturating_sub(1);
+                if self.poll_countdown == 0 {
+                    self.poll_countdown = self.poll_interval.max(1);
+                    if host_poll(self) == PollAction::Pause {
+                        return HaltReason::Paused;
+                    }
+                    //host_poll处理的命令(比如Halt)也可能要求停下
+                    if let Some(reason) = self.halt_reason.take() {
+                        return reason;
+                    }
+                }
+            }
+        }
+        HaltReason::StepLimit
+    }
+
+    pub fn run(&mut self) -> HaltReason {
         loop {
-            let mut decoder = InstDecoder::armv7();
-            decoder.set_thumb_mode(InstrSet::Thumb == self.current_instr_set());
-            decoder.set_apsr_c(self.cpu.apsr().c());
-            decoder.set_in_it_block(self.in_it_block());
-            let instruction = match decoder.decode(self) {
-                Ok(t) => t,
-                Err(_) => todo!(), //TODO 处理非法的指令
-            };
-            self.execute(instruction).unwrap();
+            match self.run_bounded(u32::MAX) {
+                HaltReason::StepLimit => {}
+                reason => return reason,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::interrupt::MODE_UNDEFINED;
+    use crate::memory::INTERNAL_SIZE;
+    use crate::serial::MockSerial;
+
+    //从0开始放入Arm指令, 每个元素是一条指令的编码
+    pub(crate) fn arm_machine(code: &[u32]) -> Box<Machine> {
+        let mut machine = Box::new(Machine::default());
+        for (i, word) in code.iter().enumerate() {
+            machine.memory.data[i * 4..i * 4 + 4].copy_from_slice(&word.to_le_bytes());
+        }
+        machine
+    }
+
+    //从0开始放入Thumb指令, 32位的指令按两个半字给出
+    pub(crate) fn thumb_machine(code: &[u16]) -> Box<Machine> {
+        let mut machine = Box::new(Machine::default());
+        for (i, halfword) in code.iter().enumerate() {
+            machine.memory.data[i * 2..i * 2 + 2].copy_from_slice(&halfword.to_le_bytes());
+        }
+        machine.set_entry_point(1);
+        machine
+    }
+
+    #[test]
+    fn immediate_carry_uses_fetched_encoding() {
+        //指令放在外部内存, 重新读编码会多发一帧
+        let mut machine = arm_machine(&[]);
+        let serial = MockSerial::new();
+        machine.attach_serial(Box::new(serial.clone()));
+        for byte in 0xe3b00102u32.to_le_bytes() {
+            serial.push_reply(&[byte]); //movs r0, #0x80000000
+        }
+        machine.set_entry_point(INTERNAL_SIZE as u32);
+        machine.step().unwrap();
+        assert_eq!(machine.cpu.regs[0], 0x80000000);
+        assert!(machine.cpu.apsr().c());
+        assert_eq!(serial.frames().len(), 4);
+    }
+
+    #[test]
+    fn arm_reads_pc_as_instruction_plus_8() {
+        let mut machine = arm_machine(&[
+            0xe1a0000f, //mov r0, pc
+            0xe28f1004, //add r1, pc, #4
+            0xe51f2004, //ldr r2, [pc, #-4]
+            0x12345678,
+        ]);
+        for _ in 0..3 {
+            machine.step().unwrap();
+        }
+        assert_eq!(machine.cpu.regs[0], 8);
+        assert_eq!(machine.cpu.regs[1], 16);
+        assert_eq!(machine.cpu.regs[2], 0x12345678);
+    }
+
+    #[test]
+    fn thumb_reads_pc_as_instruction_plus_4() {
+        let mut machine = thumb_machine(&[
+            0x4678, //mov r0, pc
+            0xf20f, 0x0108, //addw r1, pc, #8, 在半字对齐的地址上
+            0x4a01, //ldr r2, [pc, #4]
+            0xbf00, 0xbf00, 0x5678, 0x1234,
+        ]);
+        for _ in 0..3 {
+            machine.step().unwrap();
+        }
+        assert_eq!(machine.cpu.regs[0], 4);
+        assert_eq!(machine.cpu.regs[1], 12);
+        assert_eq!(machine.cpu.regs[2], 0x12345678);
+    }
+
+    #[test]
+    fn offset_is_thumb_instruction_length() {
+        let mut machine = thumb_machine(&[
+            0x2001, //movs r0, #1
+            0xf04f, 0x0102, //mov.w r1, #2
+        ]);
+        for (start, len) in [(0, 2), (2, 4)] {
+            let decoder = machine.decoder();
+            machine.mark();
+            let instruction = decoder.decode(machine.as_mut()).unwrap();
+            assert_eq!(machine.mark, start);
+            assert_eq!(machine.offset(), len);
+            assert_eq!(instruction.len().to_const(), len);
+            assert_eq!(machine.total_offset(), start + len);
+        }
+    }
+
+    #[test]
+    fn on_error_continue_skips_faulting_instruction() {
+        let code = [
+            0xe3a00203, //mov r0, #0x30000000
+            0xe5901000, //ldr r1, [r0]
+            0xe3a02001, //mov r2, #1
+        ];
+        let mut machine = arm_machine(&code);
+        assert!(matches!(
+            machine.run_bounded(3),
+            HaltReason::Error(VMError::BusError)
+        ));
+        assert_eq!(machine.cpu.regs[2], 0);
+
+        let mut machine = arm_machine(&code);
+        machine.on_error = Some(|_, _| ErrorAction::Continue);
+        assert!(matches!(machine.run_bounded(3), HaltReason::StepLimit));
+        assert_eq!(machine.cpu.regs[2], 1);
+    }
+
+    #[test]
+    fn host_poll_fires_every_poll_interval() {
+        //r7用来数host_poll被调用了几次
+        let mut machine = arm_machine(&[0xe1a00000; 16]); //mov r0, r0
+        machine.host_poll = Some(|machine| {
+            machine.cpu.regs[7] += 1;
+            PollAction::Continue
+        });
+        machine.poll_interval = 3;
+        machine.poll_countdown = 3;
+        assert!(matches!(machine.run_bounded(10), HaltReason::StepLimit));
+        assert_eq!(machine.cpu.regs[7], 3);
+
+        machine.host_poll = Some(|_| PollAction::Pause);
+        machine.poll_countdown = 3;
+        assert!(matches!(machine.run_bounded(10), HaltReason::Paused));
+        assert_eq!(machine.cpu.regs[15], 4 * 13);
+    }
+
+    #[test]
+    fn load_write_pc_interworks_from_v5() {
+        let code = [0xe590f000]; //ldr pc, [r0]
+        let mut machine = arm_machine(&code);
+        machine.cpu.regs[0] = 0x100;
+        machine.memory.data[0x100] = 0x01;
+        machine.memory.data[0x101] = 0x02;
+        machine.step().unwrap();
+        assert_eq!(machine.cpu.regs[15], 0x200);
+        assert!(machine.current_instr_set() == InstrSet::Thumb);
+
+        //v4没有交互跳转, 只是对齐到4字节
+        let mut machine = arm_machine(&code);
+        machine.arch_version = 4;
+        machine.cpu.regs[0] = 0x100;
+        machine.memory.data[0x100] = 0x03;
+        machine.memory.data[0x101] = 0x02;
+        machine.step().unwrap();
+        assert_eq!(machine.cpu.regs[15], 0x200);
+        assert!(machine.current_instr_set() == InstrSet::Arm);
+    }
+
+    #[test]
+    fn step_it_block_runs_whole_block() {
+        let mut machine = thumb_machine(&[
+            0xbf07, //ittee eq
+            0x2001, //moveq r0, #1
+            0x2102, //moveq r1, #2
+            0x2203, //movne r2, #3
+            0x2304, //movne r3, #4
+            0xbf00, //nop
+        ]);
+        assert_eq!(machine.step_it_block().unwrap(), 5);
+        assert_eq!(machine.cpu.regs[..4], [0, 0, 3, 4]);
+        assert_eq!(machine.cpu.regs[15], 10);
+        //不在IT块里时只执行一条
+        assert_eq!(machine.step_it_block().unwrap(), 1);
+    }
+
+    #[test]
+    fn invalid_opcode_does_not_panic() {
+        let code = [
+            0xe6000010, //并行加减中op1为0b00的编码, 是未定义的
+            0xe3a02001, //mov r2, #1
+        ];
+        let mut machine = arm_machine(&code);
+        assert!(matches!(
+            machine.run_bounded(2),
+            HaltReason::Error(VMError::DecodeError(_))
+        ));
+        //PC还在这条指令的开头
+        assert_eq!(machine.cpu.regs[15], 0);
+
+        let mut machine = arm_machine(&code);
+        machine.on_error = Some(|_, _| ErrorAction::Continue);
+        assert!(matches!(machine.run_bounded(2), HaltReason::StepLimit));
+        assert_eq!(machine.cpu.regs[2], 1);
+
+        //向量表的0x04正好是mov r2, #1
+        let mut machine = arm_machine(&code);
+        machine.on_error = Some(|_, _| ErrorAction::VectorToGuest);
+        assert!(matches!(machine.run_bounded(1), HaltReason::StepLimit));
+        assert_eq!(machine.cpu.regs[15], 4);
+        assert_eq!(machine.cpu.cpsr.m(), MODE_UNDEFINED);
+    }
+
+    #[test]
+    fn undecodable_len_follows_first_halfword() {
+        for (halfword, len) in [
+            (0xe000, 2),
+            (0xe800, 4),
+            (0xf000, 4),
+            (0xf800, 4),
+            (0x4600, 2),
+        ] {
+            let machine = thumb_machine(&[halfword]);
+            assert_eq!(machine.undecodable_len(), len);
         }
+        assert_eq!(arm_machine(&[]).undecodable_len(), 4);
     }
 }
@@ -1,29 +1,23 @@
 #![no_std]
 #![no_main]
 
-pub mod arithmetic;
-pub mod cpu;
-pub mod executor;
-pub mod machine;
-pub mod memory;
-pub mod protocol;
-pub mod serial;
-pub mod vmerror;
-
-use core::cell::RefCell;
+extern crate alloc;
+
+mod usart;
+
+use alloc::boxed::Box;
 use core::panic::PanicInfo;
-use cortex_m::interrupt;
-use cortex_m::interrupt::Mutex;
 use cortex_m_rt::entry;
 use linked_list_allocator::LockedHeap;
 use stm32h7::stm32h723::*;
+#[cfg(feature = "test")]
+use vm_on_stm32::machine::HaltReason;
+use vm_on_stm32::machine::Machine;
 
-use crate::machine::Machine;
-use crate::serial::ProtocolSerial;
+use crate::usart::ProtocolSerial;
 
 #[global_allocator]
 static ALLOCATOR: LockedHeap = LockedHeap::empty();
-pub static SERIAL: Mutex<RefCell<Option<ProtocolSerial>>> = Mutex::new(RefCell::new(None));
 
 const USART_BAUD: u32 = 115200;
 const USART_FREQ: u32 = 68_750_000;
@@ -150,17 +144,22 @@ fn main() -> ! {
             .enabled() // USART enable
     });
 
-    interrupt::free(|cs| *SERIAL.borrow(cs).borrow_mut() = Some(ProtocolSerial {}));
-
     let mut machine = Machine::default();
+    machine.attach_serial(Box::new(ProtocolSerial {}));
     #[cfg(feature = "test")]
     {
         let test_code = include_bytes!("../tests/test.bin");
         for i in 0..test_code.len() {
             machine.write_memory(i as u32, test_code[i]).unwrap();
         }
+        if let HaltReason::Error(error) = machine.run() {
+            panic!("{}", error.to_str());
+        }
+        loop {}
     }
-    machine.run();
+    //由电脑通过串口写入程序并控制运行, 见Machine::serve_command
+    #[cfg(not(feature = "test"))]
+    machine.serve()
 }
 
 #[inline(never)]
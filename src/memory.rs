@@ -1,13 +1,13 @@
-use cortex_m::interrupt;
+use alloc::vec::Vec;
 
 use crate::{
-    SERIAL,
     machine::Machine,
-    protocol::{Command, receive_data},
+    mmio::{MMIO_BASE, MMIO_SIZE},
+    protocol::{Command, MAX_FRAME_SIZE, receive_data},
     vmerror::VMError,
 };
 
-const INTERNAL_SIZE: usize = 1024 * 100;
+pub const INTERNAL_SIZE: usize = 1024 * 100;
 const EXTERNAL_SIZE: usize = 1024 * 1024;
 
 pub struct Memory {
@@ -28,71 +28,303 @@ impl Memory {
     }
 }
 
+//内存区域的属性, 可以组合
+pub const REGION_RAM: u8 = 1 << 0;
+pub const REGION_EXTERNAL: u8 = 1 << 1;
+pub const REGION_MMIO: u8 = 1 << 2;
+pub const REGION_READ_ONLY: u8 = 1 << 3;
+
+//每个区域编码后的字节数: 起始地址, 长度, 属性
+pub const REGION_ENCODED_SIZE: usize = 9;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MemoryRegion {
+    pub base: u32,
+    pub size: u32,
+    pub flags: u8,
+}
+
+impl MemoryRegion {
+    pub fn encode(&self) -> [u8; REGION_ENCODED_SIZE] {
+        let mut data = [0; REGION_ENCODED_SIZE];
+        data[0..4].copy_from_slice(&self.base.to_le_bytes());
+        data[4..8].copy_from_slice(&self.size.to_le_bytes());
+        data[8] = self.flags;
+        data
+    }
+
+    pub fn decode(data: &[u8]) -> Option<MemoryRegion> {
+        if data.len() != REGION_ENCODED_SIZE {
+            return None;
+        }
+        Some(MemoryRegion {
+            base: u32::from_le_bytes([data[0], data[1], data[2], data[3]]),
+            size: u32::from_le_bytes([data[4], data[5], data[6], data[7]]),
+            flags: data[8],
+        })
+    }
+}
+
+//解析QueryMemoryMap的回复
+pub fn decode_memory_map(data: &[u8]) -> Option<Vec<MemoryRegion>> {
+    if !data.len().is_multiple_of(REGION_ENCODED_SIZE) {
+        return None;
+    }
+    data.chunks(REGION_ENCODED_SIZE)
+        .map(MemoryRegion::decode)
+        .collect()
+}
+
 impl Machine {
+    pub fn memory_map(&self) -> [MemoryRegion; 3] {
+        [
+            MemoryRegion {
+                base: 0,
+                size: INTERNAL_SIZE as u32,
+                flags: REGION_RAM,
+            },
+            MemoryRegion {
+                base: INTERNAL_SIZE as u32,
+                size: EXTERNAL_SIZE as u32,
+                flags: REGION_RAM | REGION_EXTERNAL,
+            },
+            MemoryRegion {
+                base: MMIO_BASE,
+                size: MMIO_SIZE,
+                flags: REGION_MMIO,
+            },
+        ]
+    }
+
+    //address不是size的倍数时报AlignmentFault
+    pub fn check_alignment(&self, address: u32, size: u32) -> Result<(), VMError> {
+        if !address.is_multiple_of(size) {
+            return Err(VMError::AlignmentFault);
+        }
+        Ok(())
+    }
+
     pub fn read_memory(&self, address: u32) -> Result<u8, VMError> {
+        if self.is_mmio(address) {
+            let mut byte = [0; 1];
+            self.read_mmio(address, &mut byte)?;
+            return Ok(byte[0]);
+        }
         let address = address as usize;
         if address >= self.memory.size() {
             Err(VMError::BusError)
         } else if address < INTERNAL_SIZE {
+            self.check_initialized(address as u32)?;
             Ok(self.memory.data[address])
         } else {
-            interrupt::free(|cs| -> Result<u8, VMError> {
-                if let Some(serial) = SERIAL.borrow(cs).borrow_mut().as_mut() {
-                    Command::ReadMemory((address - INTERNAL_SIZE) as u32).send(serial)?;
-                    Ok(receive_data(serial)?[0])
-                } else {
-                    unreachable!()
-                }
-            })
+            //没有接串口就访问不到外部内存
+            let mut serial = self.serial.borrow_mut();
+            let serial = serial.as_mut().ok_or(VMError::BusError)?.as_mut();
+            let offset = (address - INTERNAL_SIZE) as u32;
+            if let Some(cache) = self.external_cache.borrow_mut().as_mut() {
+                return cache.read(serial, offset);
+            }
+            Command::ReadMemory(offset).send(serial)?;
+            Ok(receive_data(serial)?[0])
         }
     }
 
     pub fn read_memory_n(&self, address: u32, buf: &mut [u8]) -> Result<(), VMError> {
+        if self.is_mmio(address) {
+            return self.read_mmio(address, buf);
+        }
+        //整段都在外部内存时用一条ReadMemoryN读完, 不用每个字节都和电脑通信一次
+        //开启了缓存时按行读取, 本来就是一次读一段
+        let start = address as usize;
+        let end = start.saturating_add(buf.len());
+        if buf.len() > 1
+            && buf.len() <= MAX_FRAME_SIZE
+            && start >= INTERNAL_SIZE
+            && end <= self.memory.size()
+            && self.external_cache.borrow().is_none()
+        {
+            let mut serial = self.serial.borrow_mut();
+            let serial = serial.as_mut().ok_or(VMError::BusError)?.as_mut();
+            Command::ReadMemoryN((start - INTERNAL_SIZE) as u32, buf.len() as u32).send(serial)?;
+            let data = receive_data(serial)?;
+            if data.len() != buf.len() {
+                return Err(VMError::BusError);
+            }
+            buf.copy_from_slice(&data);
+            return Ok(());
+        }
         for i in 0..buf.len() {
-            buf[i] = self.read_memory(address + i as u32)?;
+            buf[i] = self.read_memory(address.wrapping_add(i as u32))?;
         }
         Ok(())
     }
 
+    //CPSR.E为1时按大端读写半字和字, 取指令不受影响
     pub fn read_memory_halfword(&self, address: u32) -> Result<u16, VMError> {
         let mut word_bytes: [u8; _] = [0; 2];
         self.read_memory_n(address, &mut word_bytes)?;
-        Ok(u16::from_le_bytes(word_bytes))
+        Ok(if self.cpu.cpsr.e() {
+            u16::from_be_bytes(word_bytes)
+        } else {
+            u16::from_le_bytes(word_bytes)
+        })
     }
 
     pub fn read_memory_word(&self, address: u32) -> Result<u32, VMError> {
         let mut word_bytes: [u8; _] = [0; 4];
         self.read_memory_n(address, &mut word_bytes)?;
-        Ok(u32::from_le_bytes(word_bytes))
+        Ok(if self.cpu.cpsr.e() {
+            u32::from_be_bytes(word_bytes)
+        } else {
+            u32::from_le_bytes(word_bytes)
+        })
+    }
+
+    //读取以0结尾的字符串(不含结尾的0), 最多读max个字节, 防止客户程序传了错误的地址时一直读下去
+    pub fn read_cstr(&self, address: u32, max: usize) -> Result<Vec<u8>, VMError> {
+        let mut bytes = Vec::new();
+        while bytes.len() < max {
+            let byte = self.read_memory(address.wrapping_add(bytes.len() as u32))?;
+            if byte == 0 {
+                break;
+            }
+            bytes.push(byte);
+        }
+        Ok(bytes)
     }
 
     pub fn write_memory(&mut self, address: u32, bit: u8) -> Result<(), VMError> {
+        if self.is_mmio(address) {
+            return self.write_mmio(address, &[bit]);
+        }
+        self.check_self_modifying(address);
+        self.check_exclusive_write(address);
         let address = address as usize;
         if address >= self.memory.size() {
             return Err(VMError::BusError);
         }
         if address < INTERNAL_SIZE {
-            self.memory.data[address] = bit
+            self.memory.data[address] = bit;
+            self.mark_initialized(address as u32);
         } else {
-            unimplemented!()
+            //没有接串口就访问不到外部内存
+            let serial = self
+                .serial
+                .get_mut()
+                .as_mut()
+                .ok_or(VMError::BusError)?
+                .as_mut();
+            let offset = (address - INTERNAL_SIZE) as u32;
+            if let Some(cache) = self.external_cache.get_mut().as_mut() {
+                cache.write(serial, offset, bit)?;
+            } else {
+                Command::WriteMemory(offset, bit).send(serial)?;
+            }
         }
         Ok(())
     }
 
     pub fn write_memory_n(&mut self, address: u32, buf: &[u8]) -> Result<(), VMError> {
+        //MMIO寄存器要一次写完整个值
+        if self.is_mmio(address) {
+            return self.write_mmio(address, buf);
+        }
         for i in 0..buf.len() {
-            self.write_memory(address + i as u32, buf[i])?;
+            self.write_memory(address.wrapping_add(i as u32), buf[i])?;
         }
         Ok(())
     }
 
     pub fn write_memory_halfword(&mut self, address: u32, halfword: u16) -> Result<(), VMError> {
-        self.write_memory_n(address, &halfword.to_le_bytes())?;
+        if self.cpu.cpsr.e() {
+            self.write_memory_n(address, &halfword.to_be_bytes())?;
+        } else {
+            self.write_memory_n(address, &halfword.to_le_bytes())?;
+        }
         Ok(())
     }
 
     pub fn write_memory_word(&mut self, address: u32, word: u32) -> Result<(), VMError> {
-        self.write_memory_n(address, &word.to_le_bytes())?;
+        if self.cpu.cpsr.e() {
+            self.write_memory_n(address, &word.to_be_bytes())?;
+        } else {
+            self.write_memory_n(address, &word.to_le_bytes())?;
+        }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use alloc::boxed::Box;
+    use alloc::vec;
+
+    use super::*;
+    use crate::machine::tests::arm_machine;
+    use crate::serial::MockSerial;
+
+    #[test]
+    fn external_byte_load_sends_one_read_memory() {
+        let mut machine = arm_machine(&[
+            0xe5d01000, //ldrb r1, [r0]
+        ]);
+        let serial = MockSerial::new();
+        machine.attach_serial(Box::new(serial.clone()));
+        serial.push_reply(&[0x5a]);
+        machine.cpu.regs[0] = INTERNAL_SIZE as u32 + 0x10;
+        machine.step().unwrap();
+        assert_eq!(machine.cpu.regs[1], 0x5a);
+        assert_eq!(serial.frames(), vec![(1, vec![0x10, 0, 0, 0])]);
+        assert_eq!(serial.pending_replies(), 0);
+    }
+
+    #[test]
+    fn external_word_load_sends_one_read_memory_n() {
+        let mut machine = arm_machine(&[
+            0xe5901000, //ldr r1, [r0]
+            0xe5901000, //ldr r1, [r0]
+        ]);
+        let serial = MockSerial::new();
+        machine.attach_serial(Box::new(serial.clone()));
+        serial.push_reply(&[0x78, 0x56, 0x34, 0x12]);
+        machine.cpu.regs[0] = INTERNAL_SIZE as u32 + 0x20;
+        machine.step().unwrap();
+        assert_eq!(machine.cpu.regs[1], 0x12345678);
+        let mut request = 0x20u32.to_le_bytes().to_vec();
+        request.extend(4u32.to_le_bytes());
+        assert_eq!(serial.take_frames(), vec![(22, request)]);
+        //电脑回复的字节数不对
+        serial.push_reply(&[0x78, 0x56]);
+        assert!(matches!(machine.step(), Err(VMError::BusError)));
+    }
+
+    #[test]
+    fn external_store_sends_write_memory() {
+        let mut machine = arm_machine(&[
+            0xe5c01000, //strb r1, [r0]
+            0xe5801000, //str r1, [r0]
+        ]);
+        let serial = MockSerial::new();
+        machine.attach_serial(Box::new(serial.clone()));
+        machine.cpu.regs[0] = INTERNAL_SIZE as u32 + 0x30;
+        machine.cpu.regs[1] = 0x44332211;
+        machine.step().unwrap();
+        assert_eq!(serial.take_frames(), vec![(2, vec![0x30, 0, 0, 0, 0x11])]);
+        //字按字节写出, 电脑不回复
+        machine.step().unwrap();
+        let frames: Vec<_> = (0..4)
+            .map(|i| (2, vec![0x30 + i as u8, 0, 0, 0, 0x11 * (i as u8 + 1)]))
+            .collect();
+        assert_eq!(serial.take_frames(), frames);
+    }
+
+    #[test]
+    fn read_cstr_stops_at_nul_or_max() {
+        let mut machine = arm_machine(&[]);
+        machine.memory.data[0x100..0x106].copy_from_slice(b"hello\0");
+        assert_eq!(machine.read_cstr(0x100, 64).unwrap(), b"hello");
+        //没有遇到NUL时最多读max个字节
+        assert_eq!(machine.read_cstr(0x100, 3).unwrap(), b"hel");
+        assert_eq!(machine.read_cstr(0x105, 64).unwrap(), b"");
+    }
+}
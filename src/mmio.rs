@@ -0,0 +1,254 @@
+use crate::{
+    machine::{HaltReason, Machine},
+    vmerror::VMError,
+};
+
+pub const MMIO_BASE: u32 = 0x4000_0000;
+pub const MMIO_SIZE: u32 = 0x1000;
+
+//只写, 写入后停止运行, 写入的值作为退出码
+pub const EXIT_REGISTER: u32 = MMIO_BASE;
+//只读, 当前的周期计数
+pub const CYCCNT_REGISTER: u32 = MMIO_BASE + 4;
+//写入非0的值时挂起一个软件中断, 写入0时取消; 读出是否挂起
+pub const SOFTWARE_INTERRUPT_REGISTER: u32 = MMIO_BASE + 8;
+//软件中断处理程序的地址, 可读写
+pub const SOFTWARE_INTERRUPT_HANDLER_REGISTER: u32 = MMIO_BASE + 12;
+
+//只读的模拟器信息, 客户程序可以据此判断是否运行在模拟器上, 写入忽略
+pub const INFO_BASE: u32 = MMIO_BASE + 0x100;
+//固定为"VMST"
+pub const INFO_SIGNATURE_REGISTER: u32 = INFO_BASE;
+//模拟器版本: 主版本号<<16 | 次版本号<<8 | 修订号
+pub const INFO_VERSION_REGISTER: u32 = INFO_BASE + 4;
+//Machine::arch_version
+pub const INFO_ARCH_VERSION_REGISTER: u32 = INFO_BASE + 8;
+//已经实现的功能, 见CAP_*
+pub const INFO_CAPABILITIES_REGISTER: u32 = INFO_BASE + 12;
+
+pub const INFO_SIGNATURE: u32 = u32::from_le_bytes(*b"VMST");
+pub const EMULATOR_VERSION: u32 = 0x00_01_00;
+
+pub const CAP_ARM: u32 = 1 << 0;
+pub const CAP_THUMB: u32 = 1 << 1;
+//Thumb-2的32位指令和IT块
+pub const CAP_THUMB2: u32 = 1 << 2;
+//MUL, MLA, 64位乘法
+pub const CAP_MULTIPLY: u32 = 1 << 3;
+//SADD16这类并行加减
+pub const CAP_PARALLEL: u32 = 1 << 4;
+//QADD, SSAT这类饱和运算
+pub const CAP_SATURATE: u32 = 1 << 5;
+//SDIV, UDIV
+pub const CAP_DIVIDE: u32 = 1 << 6;
+//LDREX, STREX
+pub const CAP_EXCLUSIVE: u32 = 1 << 7;
+//接了串口, 可以访问外部内存
+pub const CAP_EXTERNAL_MEMORY: u32 = 1 << 8;
+
+//可以额外注册的MMIO区域数
+pub const MAX_MMIO_HANDLERS: usize = 4;
+
+//offset是相对区域起始的偏移, buf的长度就是访问的字节数
+pub type MmioRead = fn(&Machine, u32, &mut [u8]) -> Result<(), VMError>;
+pub type MmioWrite = fn(&mut Machine, u32, &[u8]) -> Result<(), VMError>;
+
+//注册的MMIO区域, 可以覆盖一部分内存, 访问时比内存优先
+#[derive(Clone, Copy)]
+pub struct MmioHandler {
+    pub base: u32,
+    pub size: u32,
+    pub read: MmioRead,
+    pub write: MmioWrite,
+}
+
+impl MmioHandler {
+    pub fn contains(&self, address: u32) -> bool {
+        address.wrapping_sub(self.base) < self.size
+    }
+}
+
+//[base1, base1+size1)和[base2, base2+size2)是否重叠, 用u64避免区域到了地址空间末尾时溢出
+fn ranges_overlap(base1: u32, size1: u32, base2: u32, size2: u32) -> bool {
+    (base1 as u64) < base2 as u64 + size2 as u64 && (base2 as u64) < base1 as u64 + size1 as u64
+}
+
+impl Machine {
+    pub fn capabilities(&self) -> u32 {
+        let mut capabilities = CAP_ARM
+            | CAP_THUMB
+            | CAP_THUMB2
+            | CAP_MULTIPLY
+            | CAP_PARALLEL
+            | CAP_SATURATE
+            | CAP_DIVIDE
+            | CAP_EXCLUSIVE;
+        if self.serial.borrow().is_some() {
+            capabilities |= CAP_EXTERNAL_MEMORY;
+        }
+        capabilities
+    }
+
+    //注册一个MMIO区域, 和已有的区域(包括MMIO_BASE开始的内置寄存器)重叠时报错
+    pub fn register_mmio(
+        &mut self,
+        base: u32,
+        size: u32,
+        read: MmioRead,
+        write: MmioWrite,
+    ) -> Result<(), VMError> {
+        if ranges_overlap(base, size, MMIO_BASE, MMIO_SIZE)
+            || self
+                .mmio_handlers
+                .iter()
+                .flatten()
+                .any(|other| ranges_overlap(base, size, other.base, other.size))
+        {
+            return Err(VMError::MmioOverlap);
+        }
+        let slot = self
+            .mmio_handlers
+            .iter_mut()
+            .find(|slot| slot.is_none())
+            .ok_or(VMError::MmioTableFull)?;
+        *slot = Some(MmioHandler {
+            base,
+            size,
+            read,
+            write,
+        });
+        Ok(())
+    }
+
+    pub fn unregister_mmio(&mut self, base: u32) {
+        for slot in self.mmio_handlers.iter_mut() {
+            if matches!(slot, Some(handler) if handler.base == base) {
+                *slot = None;
+            }
+        }
+    }
+
+    fn mmio_handler(&self, address: u32) -> Option<MmioHandler> {
+        self.mmio_handlers
+            .iter()
+            .flatten()
+            .find(|handler| handler.contains(address))
+            .copied()
+    }
+
+    //MMIO比内存优先, 然后是内部内存, 最后是外部内存
+    pub fn is_mmio(&self, address: u32) -> bool {
+        (MMIO_BASE..MMIO_BASE + MMIO_SIZE).contains(&address)
+            || self.mmio_handler(address).is_some()
+    }
+
+    pub fn read_mmio(&self, address: u32, buf: &mut [u8]) -> Result<(), VMError> {
+        if let Some(handler) = self.mmio_handler(address) {
+            return (handler.read)(self, address - handler.base, buf);
+        }
+        match address {
+            EXIT_REGISTER => buf.fill(0),
+            _ => {
+                let value = match address {
+                    CYCCNT_REGISTER => self.cycles,
+                    SOFTWARE_INTERRUPT_REGISTER => self.software_interrupt_pending as u32,
+                    SOFTWARE_INTERRUPT_HANDLER_REGISTER => self.software_interrupt_handler,
+                    INFO_SIGNATURE_REGISTER => INFO_SIGNATURE,
+                    INFO_VERSION_REGISTER => EMULATOR_VERSION,
+                    INFO_ARCH_VERSION_REGISTER => self.arch_version,
+                    INFO_CAPABILITIES_REGISTER => self.capabilities(),
+                    _ => return Err(VMError::BusError),
+                };
+                let bytes = value.to_le_bytes();
+                let len = buf.len().min(4);
+                buf[..len].copy_from_slice(&bytes[..len]);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn write_mmio(&mut self, address: u32, buf: &[u8]) -> Result<(), VMError> {
+        if let Some(handler) = self.mmio_handler(address) {
+            return (handler.write)(self, address - handler.base, buf);
+        }
+        //按小端拼成寄存器的值, 不足4字节的高位补0
+        let mut bytes = [0; 4];
+        let len = buf.len().min(4);
+        bytes[..len].copy_from_slice(&buf[..len]);
+        let value = u32::from_le_bytes(bytes);
+        match address {
+            EXIT_REGISTER => self.halt_reason = Some(HaltReason::GuestExit(value)),
+            SOFTWARE_INTERRUPT_REGISTER => self.software_interrupt_pending = value != 0,
+            SOFTWARE_INTERRUPT_HANDLER_REGISTER => self.software_interrupt_handler = value,
+            //写入忽略
+            CYCCNT_REGISTER
+            | INFO_SIGNATURE_REGISTER
+            | INFO_VERSION_REGISTER
+            | INFO_ARCH_VERSION_REGISTER
+            | INFO_CAPABILITIES_REGISTER => {}
+            _ => return Err(VMError::BusError),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::machine::tests::arm_machine;
+
+    #[test]
+    fn exit_register_halts_with_guest_exit() {
+        let mut machine = arm_machine(&[
+            0xe3a00101, //mov r0, #0x40000000
+            0xe3a0102a, //mov r1, #42
+            0xe5801000, //str r1, [r0]
+        ]);
+        assert!(matches!(machine.run_bounded(10), HaltReason::GuestExit(42)));
+        assert_eq!(machine.cpu.regs[15], 12);
+    }
+
+    #[test]
+    fn mmio_handler_shadows_ram() {
+        let mut machine = arm_machine(&[
+            0xe5901000, //ldr r1, [r0]
+            0xe5802000, //str r2, [r0]
+        ]);
+        machine.memory.data[0x100] = 0x11;
+        machine.cpu.regs[0] = 0x100;
+        machine.cpu.regs[2] = 0x55;
+        machine
+            .register_mmio(
+                0x100,
+                4,
+                |_, _, buf| {
+                    buf.fill(0xab);
+                    Ok(())
+                },
+                //r7记录写入的字节
+                |machine, _, buf| {
+                    machine.cpu.regs[7] = buf[0] as u32;
+                    Ok(())
+                },
+            )
+            .unwrap();
+        machine.step().unwrap();
+        machine.step().unwrap();
+        assert_eq!(machine.cpu.regs[1], 0xabababab);
+        assert_eq!(machine.cpu.regs[7], 0x55);
+        assert_eq!(machine.memory.data[0x100], 0x11);
+
+        let read: MmioRead = |_, _, _| Ok(());
+        let write: MmioWrite = |_, _, _| Ok(());
+        assert!(matches!(
+            machine.register_mmio(0x102, 4, read, write),
+            Err(VMError::MmioOverlap)
+        ));
+        assert!(matches!(
+            machine.register_mmio(EXIT_REGISTER, 4, read, write),
+            Err(VMError::MmioOverlap)
+        ));
+        machine.unregister_mmio(0x100);
+        assert!(machine.register_mmio(0x102, 4, read, write).is_ok());
+    }
+}
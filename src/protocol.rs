@@ -1,13 +1,72 @@
-extern crate alloc;
-use crate::{serial::ProtocolSerial, vmerror::VMError};
+use crate::{
+    cpu::PC_INDEX,
+    machine::{HaltReason, Machine, PollAction},
+    serial::Serial,
+    vmerror::VMError,
+};
+use alloc::string::String;
+use alloc::vec;
 use alloc::vec::Vec;
 
 pub const ESCAPE_CHAR: u8 = b'\\';
 pub const FRAME_END: u8 = 0xff;
+//收到的一帧去掉转义后最多的字节数, 防止没有FRAME_END时耗尽单片机的堆
+pub const MAX_FRAME_SIZE: usize = 1024;
 
+//命令有两个方向, 每个命令前面注明了由谁发出:
+//单片机→电脑: 客户程序访问外部内存, 半主机和断点通知, 由Command::send发出, 电脑的回复见receive_data
+//电脑→单片机: 调试和测试用, 由receive_command收到, 见Machine::serve_command
 pub enum Command {
+    //单片机→电脑: 读外部内存, 参数是外部内存中的偏移, 电脑回复1个字节
+    //电脑→单片机: 读客户程序地址处的1个字节
     ReadMemory(u32),
+    //单片机→电脑: (外部内存中的偏移, 值), 电脑不回复
+    //电脑→单片机: (客户程序的地址, 值)
     WriteMemory(u32, u8),
+    //电脑→单片机: (地址, 长度, 填充的字节)
+    Fill(u32, u32, u8),
+    //电脑→单片机: (地址, 长度, CRC32), 返回是否一致和实际的CRC32
+    Compare(u32, u32, u32),
+    //电脑→单片机
+    SetBreakpoint(u32),
+    //电脑→单片机
+    ClearBreakpoint(u32),
+    //单片机→电脑: 执行到了断点的地址, 电脑不回复
+    BreakpointHit(u32),
+    //电脑→单片机: 返回每个内存区域的编码, 见MemoryRegion::encode
+    QueryMemoryMap,
+    //单片机→电脑: (外部内存中的偏移, 长度), 外部内存缓存读入一行, 电脑回复len个字节, 见cache.rs
+    //电脑→单片机: (客户程序的地址, 长度), 一次读取一段内存
+    ReadBlock(u32, u32),
+    //单片机→电脑: (外部内存中的偏移, 数据), 外部内存缓存写回一行, 电脑不回复
+    //电脑→单片机: (客户程序的地址, 数据), 一次写入一段内存
+    WriteBlock(u32, Vec<u8>),
+    //电脑→单片机: 返回r0-r15, CPSR, SPSR, 每个4字节
+    ReadRegisters,
+    //电脑→单片机: (编号, 值), 0-15是r0-r15, 16是CPSR, 17是SPSR
+    WriteRegister(u8, u32),
+    //电脑→单片机: 执行一条指令, 下面三个命令都返回停下的原因和PC, 见Machine::halt_reply
+    Step,
+    //电脑→单片机: 运行到停下为止, 运行期间可以发Halt让它停下
+    Continue,
+    //电脑→单片机: Continue期间收到时让run停下, 不单独回复, Continue回复HaltReason::Paused
+    Halt,
+    //电脑→单片机: (偏移, 长度), 读取覆盖率位图的一段, 见coverage.rs
+    ReadCoverage(u32, u32),
+    //单片机→电脑: 半主机的SYS_READC, 电脑回复一个字节, 没有输入时回复空帧
+    ReadChar,
+    //电脑→单片机: (是否清空内存, 入口地址), 让客户程序回到上电时的状态, 见Machine::reset
+    //没有给入口地址时从0开始, 入口地址的最低位为1时从Thumb状态开始
+    Reset(bool, Option<u32>),
+    //电脑→单片机: (地址, 名字), 注册一个符号, 名字是UTF-8, 见symbols.rs
+    AddSymbol(u32, Vec<u8>),
+    //电脑→单片机
+    ClearSymbols,
+    //单片机→电脑: 半主机的SYS_WRITEC, 电脑不回复
+    WriteChar(u8),
+    //单片机→电脑: (外部内存中的偏移, 长度), 和ReadMemory一样但一次读取多个字节, 电脑回复len个字节
+    //电脑→单片机: (客户程序的地址, 长度), 和ReadBlock一样
+    ReadMemoryN(u32, u32),
 }
 
 impl Command {
@@ -15,21 +74,124 @@ impl Command {
         match self {
             Command::ReadMemory(..) => 1,
             Command::WriteMemory(..) => 2,
+            Command::Fill(..) => 3,
+            Command::Compare(..) => 4,
+            Command::SetBreakpoint(..) => 5,
+            Command::ClearBreakpoint(..) => 6,
+            Command::BreakpointHit(..) => 7,
+            Command::QueryMemoryMap => 8,
+            Command::ReadBlock(..) => 9,
+            Command::WriteBlock(..) => 10,
+            Command::ReadRegisters => 11,
+            Command::WriteRegister(..) => 12,
+            Command::Step => 13,
+            Command::Continue => 14,
+            Command::Halt => 15,
+            Command::ReadCoverage(..) => 16,
+            Command::ReadChar => 17,
+            Command::Reset(..) => 18,
+            Command::AddSymbol(..) => 19,
+            Command::ClearSymbols => 20,
+            Command::WriteChar(..) => 21,
+            Command::ReadMemoryN(..) => 22,
         }
     }
 
+    //从收到的一帧还原出命令
+    pub fn parse(head: u8, data: &[u8]) -> Result<Command, VMError> {
+        Ok(match head {
+            1 => Command::ReadMemory(read_u32(data, 0)?),
+            2 => Command::WriteMemory(read_u32(data, 0)?, read_u8(data, 4)?),
+            3 => Command::Fill(read_u32(data, 0)?, read_u32(data, 4)?, read_u8(data, 8)?),
+            4 => Command::Compare(read_u32(data, 0)?, read_u32(data, 4)?, read_u32(data, 8)?),
+            5 => Command::SetBreakpoint(read_u32(data, 0)?),
+            6 => Command::ClearBreakpoint(read_u32(data, 0)?),
+            7 => Command::BreakpointHit(read_u32(data, 0)?),
+            8 => Command::QueryMemoryMap,
+            9 => Command::ReadBlock(read_u32(data, 0)?, read_u32(data, 4)?),
+            10 => Command::WriteBlock(read_u32(data, 0)?, read_rest(data, 4)?),
+            11 => Command::ReadRegisters,
+            12 => Command::WriteRegister(read_u8(data, 0)?, read_u32(data, 1)?),
+            13 => Command::Step,
+            14 => Command::Continue,
+            15 => Command::Halt,
+            16 => Command::ReadCoverage(read_u32(data, 0)?, read_u32(data, 4)?),
+            17 => Command::ReadChar,
+            18 => Command::Reset(
+                read_u8(data, 0)? != 0,
+                if data.len() > 1 {
+                    Some(read_u32(data, 1)?)
+                } else {
+                    None
+                },
+            ),
+            19 => Command::AddSymbol(read_u32(data, 0)?, read_rest(data, 4)?),
+            20 => Command::ClearSymbols,
+            21 => Command::WriteChar(read_u8(data, 0)?),
+            22 => Command::ReadMemoryN(read_u32(data, 0)?, read_u32(data, 4)?),
+            _ => return Err(VMError::InvalidCommand),
+        })
+    }
+
     pub fn data(&self) -> Vec<u8> {
         match self {
-            Command::ReadMemory(address) => address.to_le_bytes().to_vec(),
+            Command::ReadMemory(address)
+            | Command::SetBreakpoint(address)
+            | Command::ClearBreakpoint(address)
+            | Command::BreakpointHit(address) => address.to_le_bytes().to_vec(),
             Command::WriteMemory(address, value) => {
                 let mut data = address.to_le_bytes().to_vec();
                 data.extend(value.to_le_bytes().to_vec());
                 data
             }
+            Command::QueryMemoryMap
+            | Command::ReadRegisters
+            | Command::Step
+            | Command::Continue
+            | Command::Halt
+            | Command::ReadChar
+            | Command::ClearSymbols => Vec::new(),
+            Command::WriteChar(char) => vec![*char],
+            Command::Reset(clear_memory, entry) => {
+                let mut data = vec![*clear_memory as u8];
+                if let Some(entry) = entry {
+                    data.extend(entry.to_le_bytes());
+                }
+                data
+            }
+            Command::WriteRegister(index, value) => {
+                let mut data = vec![*index];
+                data.extend(value.to_le_bytes());
+                data
+            }
+            Command::Fill(address, len, value) => {
+                let mut data = address.to_le_bytes().to_vec();
+                data.extend(len.to_le_bytes());
+                data.push(*value);
+                data
+            }
+            Command::ReadBlock(address, len)
+            | Command::ReadMemoryN(address, len)
+            | Command::ReadCoverage(address, len) => {
+                let mut data = address.to_le_bytes().to_vec();
+                data.extend(len.to_le_bytes());
+                data
+            }
+            Command::WriteBlock(address, bytes) | Command::AddSymbol(address, bytes) => {
+                let mut data = address.to_le_bytes().to_vec();
+                data.extend(bytes);
+                data
+            }
+            Command::Compare(address, len, crc) => {
+                let mut data = address.to_le_bytes().to_vec();
+                data.extend(len.to_le_bytes());
+                data.extend(crc.to_le_bytes());
+                data
+            }
         }
     }
 
-    pub fn send(&self, serial: &mut ProtocolSerial) -> Result<(), VMError> {
+    pub fn send(&self, serial: &mut dyn Serial) -> Result<(), VMError> {
         ensure_ready(serial)?;
         serial.write(self.head())?;
         for i in self.data() {
@@ -44,7 +206,7 @@ impl Command {
     }
 }
 
-pub fn ensure_ready(serial: &mut ProtocolSerial) -> Result<(), VMError> {
+pub fn ensure_ready(serial: &mut dyn Serial) -> Result<(), VMError> {
     loop {
         serial.write(0xaa)?;
         serial.flush()?;
@@ -55,7 +217,7 @@ pub fn ensure_ready(serial: &mut ProtocolSerial) -> Result<(), VMError> {
     Ok(())
 }
 
-pub fn receive_data(serial: &mut ProtocolSerial) -> Result<Vec<u8>, VMError> {
+pub fn receive_data(serial: &mut dyn Serial) -> Result<Vec<u8>, VMError> {
     let mut data = Vec::new();
     let mut escape = false;
     loop {
@@ -66,9 +228,623 @@ pub fn receive_data(serial: &mut ProtocolSerial) -> Result<Vec<u8>, VMError> {
         } else if !escape && byte == FRAME_END {
             break;
         } else {
+            if data.len() >= MAX_FRAME_SIZE {
+                return Err(VMError::FrameTooLong);
+            }
             escape = false;
             data.push(byte);
         }
     }
     Ok(data)
 }
+
+//电脑发来命令时方向和send相反: 电脑发0xaa, 单片机回0x55, 然后电脑发出命令编号, 转义后的数据和FRAME_END
+pub fn receive_command(serial: &mut dyn Serial) -> Result<Command, VMError> {
+    while serial.read()? != 0xaa {}
+    serial.write(0x55)?;
+    serial.flush()?;
+    let head = serial.read()?;
+    let mut data = Vec::new();
+    let mut escape = false;
+    loop {
+        let byte = serial.read()?;
+        if !escape && byte == ESCAPE_CHAR {
+            escape = true;
+        } else if !escape && byte == FRAME_END {
+            break;
+        } else {
+            if data.len() >= MAX_FRAME_SIZE {
+                return Err(VMError::FrameTooLong);
+            }
+            escape = false;
+            data.push(byte);
+        }
+    }
+    Command::parse(head, &data)
+}
+
+//回复电脑发来的命令, 和receive_data方向相反: 电脑每发一个0xa5, 单片机发一个字节, 最后是FRAME_END
+pub fn send_reply(serial: &mut dyn Serial, data: &[u8]) -> Result<(), VMError> {
+    let mut bytes = Vec::new();
+    for &byte in data {
+        if byte == ESCAPE_CHAR || byte == FRAME_END {
+            bytes.push(ESCAPE_CHAR);
+        }
+        bytes.push(byte);
+    }
+    bytes.push(FRAME_END);
+    for byte in bytes {
+        while serial.read()? != 0xa5 {}
+        serial.write(byte)?;
+    }
+    serial.flush()
+}
+
+fn read_u8(data: &[u8], at: usize) -> Result<u8, VMError> {
+    data.get(at).copied().ok_or(VMError::InvalidCommand)
+}
+
+fn read_u32(data: &[u8], at: usize) -> Result<u32, VMError> {
+    let bytes = data.get(at..at + 4).ok_or(VMError::InvalidCommand)?;
+    Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn read_rest(data: &[u8], at: usize) -> Result<Vec<u8>, VMError> {
+    Ok(data.get(at..).ok_or(VMError::InvalidCommand)?.to_vec())
+}
+
+//CRC-32(IEEE), 和python的zlib.crc32一致, 可以分段计算
+pub fn crc32(crc: u32, data: &[u8]) -> u32 {
+    let mut crc = !crc;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                crc >> 1 ^ 0xedb88320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+//每次处理的字节数, 避免一次申请太多堆内存
+const CHUNK_SIZE: usize = 64;
+
+impl Machine {
+    //处理电脑发来的命令, 返回要回复的数据
+    pub fn handle_command(&mut self, command: Command) -> Result<Vec<u8>, VMError> {
+        Ok(match command {
+            Command::ReadMemory(address) => vec![self.read_memory(address)?],
+            Command::WriteMemory(address, value) => {
+                self.write_memory(address, value)?;
+                Vec::new()
+            }
+            Command::Fill(address, len, value) => {
+                let chunk = [value; CHUNK_SIZE];
+                let mut offset = 0;
+                while offset < len {
+                    let n = (len - offset).min(CHUNK_SIZE as u32);
+                    self.write_memory_n(address.wrapping_add(offset), &chunk[..n as usize])?;
+                    offset += n;
+                }
+                Vec::new()
+            }
+            Command::Compare(address, len, expected) => {
+                let mut chunk = [0; CHUNK_SIZE];
+                let mut crc = 0;
+                let mut offset = 0;
+                while offset < len {
+                    let n = (len - offset).min(CHUNK_SIZE as u32);
+                    let buf = &mut chunk[..n as usize];
+                    self.read_memory_n(address.wrapping_add(offset), buf)?;
+                    crc = crc32(crc, buf);
+                    offset += n;
+                }
+                let mut data = vec![(crc == expected) as u8];
+                data.extend(crc.to_le_bytes());
+                data
+            }
+            Command::ReadBlock(address, len) | Command::ReadMemoryN(address, len) => {
+                //回复还有1个字节的状态, 要在申请内存之前检查, 否则len太大时会耗尽堆
+                if len as usize + 1 > MAX_FRAME_SIZE {
+                    return Err(VMError::FrameTooLong);
+                }
+                let mut data = vec![0; len as usize];
+                self.read_memory_n(address, &mut data)?;
+                data
+            }
+            Command::WriteBlock(address, data) => {
+                self.write_memory_n(address, &data)?;
+                Vec::new()
+            }
+            Command::SetBreakpoint(address) => {
+                self.set_breakpoint(address)?;
+                Vec::new()
+            }
+            Command::ClearBreakpoint(address) => {
+                self.clear_breakpoint(address);
+                Vec::new()
+            }
+            Command::ReadRegisters => {
+                let mut data = Vec::new();
+                for reg in self.cpu.regs {
+                    data.extend(reg.to_le_bytes());
+                }
+                data.extend(self.cpu.cpsr.0.to_le_bytes());
+                data.extend(self.cpu.spsr().0.to_le_bytes());
+                data
+            }
+            Command::WriteRegister(index, value) => {
+                match index {
+                    0..=14 => self.cpu.regs[index as usize] = value,
+                    15 => {
+                        self.cpu.regs[PC_INDEX] = value;
+                        //PC被改了, 不再是停在断点上
+                        self.resume_from_breakpoint = None;
+                    }
+                    16 => self.cpu.cpsr.0 = value,
+                    17 => self.cpu.spsr_mut().0 = value,
+                    _ => return Err(VMError::InvalidCommand),
+                }
+                Vec::new()
+            }
+            Command::Step => {
+                let reason = self.run_bounded(1);
+                self.halt_reply(reason)
+            }
+            Command::Continue => {
+                let host_poll = self.host_poll.replace(poll_halt);
+                let reason = self.run();
+                self.host_poll = host_poll;
+                self.halt_reply(reason)
+            }
+            Command::Halt => {
+                self.halt_reason = Some(HaltReason::Paused);
+                self.halt_reply(HaltReason::Paused)
+            }
+            Command::Reset(clear_memory, entry) => {
+                if clear_memory {
+                    self.reset();
+                } else {
+                    self.reset_execution_state();
+                }
+                if let Some(entry) = entry {
+                    self.set_entry_point(entry);
+                }
+                Vec::new()
+            }
+            Command::AddSymbol(address, name) => {
+                let name = String::from_utf8(name).map_err(|_| VMError::InvalidCommand)?;
+                self.add_symbol(address, name);
+                Vec::new()
+            }
+            Command::ClearSymbols => {
+                self.clear_symbols();
+                Vec::new()
+            }
+            Command::ReadCoverage(offset, len) => {
+                //没有开启覆盖率或者超出了位图
+                let coverage = self.coverage.as_ref().ok_or(VMError::InvalidCommand)?;
+                let range = offset as usize..(offset as usize).saturating_add(len as usize);
+                coverage.get(range).ok_or(VMError::InvalidCommand)?.to_vec()
+            }
+            Command::QueryMemoryMap => {
+                let mut data = Vec::new();
+                for region in self.memory_map() {
+                    data.extend(region.encode());
+                }
+                data
+            }
+            //只会由单片机发出
+            Command::BreakpointHit(..) | Command::ReadChar | Command::WriteChar(..) => {
+                return Err(VMError::InvalidCommand);
+            }
+        })
+    }
+
+    //接收并处理电脑发来的一条命令, 然后回复
+    //回复的第一个字节为0时后面是handle_command返回的数据, 为1时后面是VMError::to_str
+    pub fn serve_command(&mut self) -> Result<(), VMError> {
+        let command = {
+            let serial = self.serial.get_mut().as_mut().ok_or(VMError::BusError)?;
+            receive_command(serial.as_mut())
+        };
+        let result = command.and_then(|command| self.handle_command(command));
+        //命令之间客户程序本来就是停着的, Halt不能留下halt_reason, 否则下一次Continue只执行一条指令
+        self.halt_reason = None;
+        let mut reply = Vec::new();
+        match result {
+            Ok(data) => {
+                reply.push(0);
+                reply.extend(data);
+            }
+            Err(error) => {
+                reply.push(1);
+                reply.extend(error.to_str().as_bytes());
+            }
+        }
+        let serial = self.serial.get_mut().as_mut().ok_or(VMError::BusError)?;
+        send_reply(serial.as_mut(), &reply)
+    }
+
+    //板子上的主循环: 一直处理电脑发来的命令, 由电脑写入程序并控制运行
+    pub fn serve(&mut self) -> ! {
+        loop {
+            //串口出错时这条命令没有回复, 电脑重发即可
+            let _ = self.serve_command();
+        }
+    }
+
+    //停下的原因(1字节), 附带的值(4字节), PC(4字节)
+    //原因: 0 StepLimit, 1 GuestExit(退出码), 2 BreakpointHit(地址), 3 Paused, 4 Error, 5 WaitForInterrupt
+    pub fn halt_reply(&self, reason: HaltReason) -> Vec<u8> {
+        let (code, value) = match reason {
+            HaltReason::StepLimit => (0, 0),
+            HaltReason::GuestExit(exit_code) => (1, exit_code),
+            HaltReason::BreakpointHit(address) => (2, address),
+            HaltReason::Paused => (3, 0),
+            HaltReason::Error(..) => (4, 0),
+            HaltReason::WaitForInterrupt => (5, 0),
+        };
+        let mut data = vec![code];
+        data.extend(value.to_le_bytes());
+        data.extend(self.cpu.regs[PC_INDEX].to_le_bytes());
+        data
+    }
+}
+
+//Continue期间的host_poll: 电脑发来Halt时暂停
+//运行中只认Halt, 其它命令读完后丢弃, 电脑要先Halt再发别的命令
+fn poll_halt(machine: &mut Machine) -> PollAction {
+    let Some(serial) = machine.serial.get_mut().as_mut() else {
+        return PollAction::Continue;
+    };
+    if !serial.pending() {
+        return PollAction::Continue;
+    }
+    match receive_command(serial.as_mut()) {
+        Ok(Command::Halt) => PollAction::Pause,
+        _ => PollAction::Continue,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::boxed::Box;
+
+    use super::*;
+    use crate::breakpoint::MAX_BREAKPOINTS;
+    use crate::coverage::COVERAGE_SIZE;
+    use crate::cpu::CPU;
+    use crate::machine::tests::arm_machine;
+    use crate::memory::{INTERNAL_SIZE, decode_memory_map};
+    use crate::serial::MockSerial;
+
+    //code见arm_machine
+    fn serving_machine(code: &[u32]) -> (Box<Machine>, MockSerial) {
+        let mut machine = arm_machine(code);
+        let serial = MockSerial::new();
+        machine.attach_serial(Box::new(serial.clone()));
+        (machine, serial)
+    }
+
+    //依次处理commands, 返回每条命令的回复
+    fn serve(machine: &mut Machine, serial: &MockSerial, commands: &[Command]) -> Vec<Vec<u8>> {
+        let before = serial.command_replies().len();
+        for command in commands {
+            serial.push_command(command);
+        }
+        for _ in commands {
+            machine.serve_command().unwrap();
+        }
+        serial.command_replies()[before..].to_vec()
+    }
+
+    //Step, Continue, Halt成功时的回复, 见halt_reply
+    fn halt(code: u8, value: u32, pc: u32) -> Vec<u8> {
+        let mut reply = vec![0, code];
+        reply.extend(value.to_le_bytes());
+        reply.extend(pc.to_le_bytes());
+        reply
+    }
+
+    #[test]
+    fn fill_and_compare_round_trip() {
+        let (mut machine, serial) = serving_machine(&[]);
+        let crc = crc32(0, &[0xab; 8]);
+        let replies = serve(
+            &mut machine,
+            &serial,
+            &[
+                Command::Fill(0x100, 8, 0xab),
+                Command::Compare(0x100, 8, crc),
+                Command::Compare(0x100, 9, crc),
+            ],
+        );
+        assert_eq!(machine.memory.data[0x100..0x108], [0xab; 8]);
+        assert_eq!(machine.memory.data[0x108], 0);
+        assert_eq!(replies[0], [0]);
+        let mut matched = vec![0, 1];
+        matched.extend(crc.to_le_bytes());
+        assert_eq!(replies[1], matched);
+        assert_eq!(replies[2][..2], [0, 0]);
+    }
+
+    #[test]
+    fn fill_and_compare_encoding() {
+        let fill = Command::Fill(0x12345678, 0x100, 0xff);
+        assert!(matches!(
+            Command::parse(fill.head(), &fill.data()),
+            Ok(Command::Fill(0x12345678, 0x100, 0xff))
+        ));
+        let compare = Command::Compare(0x10, 0x20, 0xdeadbeef);
+        assert!(matches!(
+            Command::parse(compare.head(), &compare.data()),
+            Ok(Command::Compare(0x10, 0x20, 0xdeadbeef))
+        ));
+        //数据不够长
+        assert!(Command::parse(fill.head(), &fill.data()[..8]).is_err());
+    }
+
+    #[test]
+    fn error_is_replied_with_its_name() {
+        let (mut machine, serial) = serving_machine(&[]);
+        //只会由单片机发出的命令
+        let replies = serve(
+            &mut machine,
+            &serial,
+            &[Command::ClearSymbols, Command::ReadChar],
+        );
+        assert_eq!(replies[0], [0]);
+        let mut error = vec![1];
+        error.extend(VMError::InvalidCommand.to_str().as_bytes());
+        assert_eq!(replies[1], error);
+    }
+
+    #[test]
+    fn breakpoint_set_hit_clear() {
+        //内存全是0, 也就是条件不成立的ANDEQ, 相当于一串NOP
+        let (mut machine, serial) = serving_machine(&[]);
+        let replies = serve(
+            &mut machine,
+            &serial,
+            &[
+                Command::SetBreakpoint(8),
+                Command::SetBreakpoint(16),
+                Command::Continue,
+                Command::ClearBreakpoint(16),
+                Command::Continue,
+            ],
+        );
+        assert_eq!(replies[..2], [[0], [0]]);
+        assert_eq!(replies[2], halt(2, 8, 8));
+        //停下时通知了电脑
+        assert_eq!(serial.take_frames()[0], (7, 8u32.to_le_bytes().to_vec()));
+        assert_eq!(replies[3], [0]);
+        //清除后不再停在16, 一直执行到取外部内存的指令时出错
+        assert_eq!(replies[4], halt(4, 0, INTERNAL_SIZE as u32));
+    }
+
+    #[test]
+    fn breakpoint_table_full() {
+        let (mut machine, serial) = serving_machine(&[]);
+        let commands: Vec<Command> = (0..=MAX_BREAKPOINTS as u32)
+            .map(|i| Command::SetBreakpoint(i * 4))
+            .collect();
+        let replies = serve(&mut machine, &serial, &commands);
+        assert!(replies[..MAX_BREAKPOINTS].iter().all(|reply| reply == &[0]));
+        let mut error = vec![1];
+        error.extend(VMError::BreakpointTableFull.to_str().as_bytes());
+        assert_eq!(replies[MAX_BREAKPOINTS], error);
+    }
+
+    #[test]
+    fn memory_map_round_trip() {
+        let (mut machine, serial) = serving_machine(&[]);
+        let replies = serve(&mut machine, &serial, &[Command::QueryMemoryMap]);
+        assert_eq!(replies[0][0], 0);
+        let regions = decode_memory_map(&replies[0][1..]).unwrap();
+        assert_eq!(regions, machine.memory_map());
+        //长度不是区域编码的整数倍
+        assert!(decode_memory_map(&replies[0][2..]).is_none());
+    }
+
+    #[test]
+    fn write_and_read_registers() {
+        let (mut machine, serial) = serving_machine(&[]);
+        let replies = serve(
+            &mut machine,
+            &serial,
+            &[
+                Command::WriteRegister(0, 0x12345678),
+                Command::WriteRegister(15, 0x20),
+                Command::WriteRegister(18, 0),
+                Command::ReadRegisters,
+                Command::Step,
+            ],
+        );
+        assert_eq!(replies[..2], [[0], [0]]);
+        assert_eq!(replies[2][0], 1);
+        let registers = &replies[3][1..];
+        assert_eq!(registers.len(), 18 * 4);
+        assert_eq!(registers[0..4], 0x12345678u32.to_le_bytes());
+        assert_eq!(registers[60..64], 0x20u32.to_le_bytes());
+        assert_eq!(registers[64..68], machine.cpu.cpsr.0.to_le_bytes());
+        //从写入的PC开始执行
+        assert_eq!(replies[4], halt(0, 0, 0x24));
+    }
+
+    #[test]
+    fn step_continue_halt() {
+        let (mut machine, serial) = serving_machine(&[
+            0xe3a00101, //mov r0, #0x40000000
+            0xe3a0102a, //mov r1, #42
+            0xe5801000, //str r1, [r0], 写EXIT寄存器
+        ]);
+        let replies = serve(
+            &mut machine,
+            &serial,
+            &[Command::Step, Command::Halt, Command::Continue],
+        );
+        assert_eq!(replies[0], halt(0, 0, 4));
+        assert_eq!(replies[1], halt(3, 0, 4));
+        assert_eq!(replies[2], halt(1, 42, 12));
+    }
+
+    #[test]
+    fn halt_during_continue() {
+        let (mut machine, serial) = serving_machine(&[
+            0xe3a00001, //mov r0, #1
+            0xeafffffe, //b .
+        ]);
+        machine.poll_interval = 16;
+        serial.push_command(&Command::Continue);
+        serial.push_interrupt(&Command::Halt);
+        machine.serve_command().unwrap();
+        //Halt没有单独的回复
+        assert_eq!(serial.command_replies(), vec![halt(3, 0, 4)]);
+        //Continue结束后恢复原来的host_poll
+        assert!(machine.host_poll.is_none());
+    }
+
+    #[test]
+    fn read_coverage() {
+        let (mut machine, serial) = serving_machine(&[]);
+        //没有开启覆盖率
+        let replies = serve(&mut machine, &serial, &[Command::ReadCoverage(0, 1)]);
+        assert_eq!(replies[0][0], 1);
+        machine.enable_coverage();
+        let replies = serve(
+            &mut machine,
+            &serial,
+            &[
+                Command::Step,
+                Command::Step,
+                Command::Step,
+                Command::ReadCoverage(0, 2),
+                Command::ReadCoverage(COVERAGE_SIZE as u32 - 1, 2),
+            ],
+        );
+        //地址0, 4, 8是第0, 2, 4个半字
+        assert_eq!(replies[3], [0, 0b10101, 0]);
+        //超出了位图
+        assert_eq!(replies[4][0], 1);
+    }
+
+    #[test]
+    fn reset_restores_power_on_registers() {
+        let (mut machine, serial) = serving_machine(&[]);
+        let power_on = CPU::default();
+        let replies = serve(
+            &mut machine,
+            &serial,
+            &[
+                Command::WriteRegister(0, 5),
+                Command::WriteRegister(15, 0x40),
+                Command::WriteMemory(0x100, 0xab),
+                Command::Reset(false, None),
+                Command::ReadRegisters,
+            ],
+        );
+        assert_eq!(replies[3], [0]);
+        let registers = &replies[4][1..];
+        for (i, reg) in power_on.regs.iter().enumerate() {
+            assert_eq!(registers[i * 4..i * 4 + 4], reg.to_le_bytes());
+        }
+        assert_eq!(registers[64..68], power_on.cpsr.0.to_le_bytes());
+        //没有清空内存
+        assert_eq!(machine.memory.data[0x100], 0xab);
+
+        let replies = serve(&mut machine, &serial, &[Command::Reset(true, Some(0x201))]);
+        assert_eq!(replies[0], [0]);
+        assert_eq!(machine.memory.data[0x100], 0);
+        assert_eq!(machine.cpu.regs[PC_INDEX], 0x200);
+        assert!(machine.cpu.cpsr.t());
+    }
+
+    #[test]
+    fn add_and_clear_symbols() {
+        let (mut machine, serial) = serving_machine(&[]);
+        let replies = serve(
+            &mut machine,
+            &serial,
+            &[
+                Command::AddSymbol(0x200, b"helper".to_vec()),
+                Command::AddSymbol(0x100, b"main".to_vec()),
+                //名字不是UTF-8
+                Command::AddSymbol(0x300, vec![0xff, 0xfe]),
+            ],
+        );
+        assert_eq!(replies[..2], [[0], [0]]);
+        assert_eq!(replies[2][0], 1);
+        assert_eq!(machine.symbolize(0x104), Some(("main", 4)));
+        assert_eq!(machine.symbolize(0x2ff), Some(("helper", 0xff)));
+        assert_eq!(machine.symbolize(0xff), None);
+        let replies = serve(&mut machine, &serial, &[Command::ClearSymbols]);
+        assert_eq!(replies[0], [0]);
+        assert_eq!(machine.symbolize(0x104), None);
+    }
+
+    #[test]
+    fn read_memory_n_from_host() {
+        let (mut machine, serial) = serving_machine(&[0x44332211]);
+        let command = Command::ReadMemoryN(1, 3);
+        assert!(matches!(
+            Command::parse(command.head(), &command.data()),
+            Ok(Command::ReadMemoryN(1, 3))
+        ));
+        let replies = serve(&mut machine, &serial, &[command]);
+        assert_eq!(replies[0], [0, 0x22, 0x33, 0x44]);
+    }
+
+    #[test]
+    fn receive_data_rejects_long_frame() {
+        let mut serial = MockSerial::new();
+        serial.push_reply(&[0xff; MAX_FRAME_SIZE]);
+        assert_eq!(receive_data(&mut serial).unwrap(), [0xff; MAX_FRAME_SIZE]);
+        serial.push_reply(&[0x5a; MAX_FRAME_SIZE + 1]);
+        assert!(matches!(
+            receive_data(&mut serial),
+            Err(VMError::FrameTooLong)
+        ));
+    }
+
+    #[test]
+    fn oversized_read_block_is_rejected() {
+        let (mut machine, serial) = serving_machine(&[]);
+        let max = MAX_FRAME_SIZE as u32 - 1;
+        let replies = serve(
+            &mut machine,
+            &serial,
+            &[
+                Command::ReadBlock(0, 0xffff_ffff),
+                Command::ReadBlock(0, max + 1),
+                Command::ReadBlock(0, max),
+            ],
+        );
+        let mut too_long = vec![1];
+        too_long.extend(b"Frame Too Long");
+        assert_eq!(replies[0], too_long);
+        assert_eq!(replies[1], too_long);
+        assert_eq!(replies[2].len(), MAX_FRAME_SIZE);
+    }
+
+    #[test]
+    fn oversized_read_memory_n_is_rejected() {
+        let (mut machine, serial) = serving_machine(&[]);
+        let replies = serve(
+            &mut machine,
+            &serial,
+            &[
+                Command::ReadMemoryN(0, 0xffff_ffff),
+                Command::ReadMemoryN(0, 4),
+            ],
+        );
+        let mut too_long = vec![1];
+        too_long.extend(b"Frame Too Long");
+        assert_eq!(replies[0], too_long);
+        assert_eq!(replies[1], [0, 0, 0, 0, 0]);
+    }
+}
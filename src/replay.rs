@@ -0,0 +1,107 @@
+use alloc::vec::Vec;
+use yaxpeax_arch::{Decoder, U8Reader};
+
+use crate::cpu::PC_INDEX;
+use crate::machine::Machine;
+use crate::protocol::crc32;
+use crate::vmerror::VMError;
+
+//执行过的指令的记录, 可以附在bug报告里, 在电脑上按原样重新执行
+pub struct ReplayLog {
+    //开始记录时的状态哈希, 重放前用来确认初始状态一致
+    pub initial_hash: u32,
+    //每条指令取到的字节, 依次拼接
+    pub bytes: Vec<u8>,
+    //每条指令的字节数
+    pub lengths: Vec<u8>,
+}
+
+impl Machine {
+    //CPU和内部内存的CRC32, 外部内存在电脑上, 不计算在内
+    pub fn state_hash(&self) -> u32 {
+        let mut crc = 0;
+        for reg in self.cpu.regs {
+            crc = crc32(crc, &reg.to_le_bytes());
+        }
+        crc = crc32(crc, &self.cpu.cpsr.0.to_le_bytes());
+        for spsr in self.cpu.spsrs {
+            crc = crc32(crc, &spsr.0.to_le_bytes());
+        }
+        crc32(crc, &self.memory.data)
+    }
+
+    pub fn start_recording(&mut self) {
+        self.recording = Some(ReplayLog {
+            initial_hash: self.state_hash(),
+            bytes: Vec::new(),
+            lengths: Vec::new(),
+        });
+    }
+
+    pub fn stop_recording(&mut self) -> Option<ReplayLog> {
+        self.recording.take()
+    }
+
+    //由step在解码后调用, 此时encoding的前len个字节正好是这条指令
+    pub fn record_fetched(&mut self, len: u32) {
+        if let Some(log) = &mut self.recording {
+            log.bytes.extend_from_slice(&self.encoding[..len as usize]);
+            log.lengths.push(len as u8);
+        }
+    }
+
+    //从和记录时相同的状态开始, 执行记录下来的字节而不是从内存取指令
+    pub fn replay(&mut self, log: &ReplayLog) -> Result<(), VMError> {
+        if self.state_hash() != log.initial_hash {
+            return Err(VMError::ReplayMismatch);
+        }
+        let mut bytes = log.bytes.as_slice();
+        for &len in &log.lengths {
+            let (fetched, rest) = bytes
+                .split_at_checked(len as usize)
+                .ok_or(VMError::ReplayMismatch)?;
+            bytes = rest;
+            let instruction = self
+                .decoder()
+                .decode(&mut U8Reader::new(fetched))
+                .map_err(|_| VMError::ReplayMismatch)?;
+            //和取指令一样让PC越过这条指令
+            self.mark = self.cpu.regs[PC_INDEX];
+            self.encoding[..fetched.len()].copy_from_slice(fetched);
+            self.cpu.regs[PC_INDEX] = self.cpu.regs[PC_INDEX].wrapping_add(len as u32);
+            self.execute(instruction)?;
+            self.cycles = self.cycles.wrapping_add(1);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::machine::tests::arm_machine;
+
+    #[test]
+    fn replay_reaches_recorded_state() {
+        let code = [
+            0xe3a00000, //mov r0, #0
+            0xe3a01c01, //mov r1, #0x100
+            0xe2800001, //add r0, r0, #1
+            0xe3500003, //cmp r0, #3
+            0x1afffffc, //bne 8
+            0xe5810000, //str r0, [r1]
+        ];
+        let mut recorded = arm_machine(&code);
+        recorded.start_recording();
+        for _ in 0..12 {
+            recorded.step().unwrap();
+        }
+        let log = recorded.stop_recording().unwrap();
+        assert_eq!(log.lengths.len(), 12);
+        assert_eq!(recorded.memory.data[0x100], 3);
+
+        let mut replayed = arm_machine(&code);
+        replayed.replay(&log).unwrap();
+        assert_eq!(replayed.cpu.regs, recorded.cpu.regs);
+        assert_eq!(replayed.state_hash(), recorded.state_hash());
+    }
+}
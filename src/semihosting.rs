@@ -0,0 +1,105 @@
+use crate::{
+    machine::Machine,
+    protocol::{Command, receive_data},
+    vmerror::VMError,
+};
+
+//BKPT 0xab是Thumb下的半主机调用, r0是操作编号, r1是参数块的地址, 结果写回r0
+pub const SEMIHOSTING_BKPT: u32 = 0xab;
+//老的半主机调用用SVC, Arm下是SVC 0x123456, Thumb下是SVC 0xab
+pub const SEMIHOSTING_SVC_ARM: u32 = 0x123456;
+pub const SEMIHOSTING_SVC_THUMB: u32 = 0xab;
+
+//向电脑输出一个字符, r1指向这个字符
+pub const SYS_WRITEC: u32 = 0x03;
+//从电脑读取一个字符
+pub const SYS_READC: u32 = 0x07;
+
+//执行SVC时调用, 参数是SVC的立即数, 返回后继续执行下一条指令
+pub type SvcHandler = fn(&mut Machine, u32) -> Result<(), VMError>;
+
+//把SVC当作半主机调用的SvcHandler, 其它立即数的SVC忽略
+pub fn semihosting_svc(machine: &mut Machine, imm: u32) -> Result<(), VMError> {
+    match imm {
+        SEMIHOSTING_SVC_ARM | SEMIHOSTING_SVC_THUMB => machine.semihosting(),
+        _ => Ok(()),
+    }
+}
+
+//没有输入(或者没有接串口)时SYS_READC返回的值
+pub const READC_NO_INPUT: u32 = u32::MAX;
+
+impl Machine {
+    pub fn semihosting(&mut self) -> Result<(), VMError> {
+        let result = match self.cpu.regs[0] {
+            SYS_WRITEC => {
+                self.host_write_char(self.read_memory(self.cpu.regs[1])?)?;
+                //r0的值不变
+                self.cpu.regs[0]
+            }
+            SYS_READC => self.host_read_char()?,
+            //不支持的操作按失败返回-1
+            _ => u32::MAX,
+        };
+        self.cpu.regs[0] = result;
+        Ok(())
+    }
+
+    //电脑不回复, 没有接串口时丢掉
+    pub fn host_write_char(&self, char: u8) -> Result<(), VMError> {
+        let mut serial = self.serial.borrow_mut();
+        let Some(serial) = serial.as_mut() else {
+            return Ok(());
+        };
+        Command::WriteChar(char).send(serial.as_mut())
+    }
+
+    //电脑回复1个字节, 没有输入时回复空帧
+    pub fn host_read_char(&self) -> Result<u32, VMError> {
+        let mut serial = self.serial.borrow_mut();
+        let Some(serial) = serial.as_mut() else {
+            return Ok(READC_NO_INPUT);
+        };
+        Command::ReadChar.send(serial.as_mut())?;
+        let data = receive_data(serial.as_mut())?;
+        Ok(data.first().map_or(READC_NO_INPUT, |&byte| byte as u32))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::boxed::Box;
+
+    use super::*;
+    use crate::machine::tests::thumb_machine;
+    use crate::serial::MockSerial;
+
+    #[test]
+    fn readc_reads_char_from_host() {
+        let mut machine = thumb_machine(&[
+            0x2007, //movs r0, #7
+            0xbeab, //bkpt 0xab
+            0x2007, //movs r0, #7
+            0xbeab, //bkpt 0xab
+        ]);
+        let serial = MockSerial::new();
+        machine.attach_serial(Box::new(serial.clone()));
+        serial.push_reply(b"x");
+        //电脑没有输入时回复空帧
+        serial.push_reply(&[]);
+        machine.step().unwrap();
+        machine.step().unwrap();
+        assert_eq!(machine.cpu.regs[0], b'x' as u32);
+        machine.step().unwrap();
+        machine.step().unwrap();
+        assert_eq!(machine.cpu.regs[0], READC_NO_INPUT);
+        let head = Command::ReadChar.head();
+        assert_eq!(
+            serial.frames(),
+            [
+                (head, Command::ReadChar.data()),
+                (head, Command::ReadChar.data())
+            ]
+        );
+    }
+}
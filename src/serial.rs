@@ -1,29 +1,205 @@
-use stm32h7::stm32h723::USART2;
+#[cfg(test)]
+use alloc::{collections::VecDeque, rc::Rc, vec, vec::Vec};
+#[cfg(test)]
+use core::cell::RefCell;
 
+#[cfg(test)]
+use crate::protocol::{Command, ESCAPE_CHAR, FRAME_END};
 use crate::vmerror::VMError;
 
-pub struct ProtocolSerial;
+//和电脑通信用的串口, 板子上由USART2实现
+pub trait Serial {
+    fn write(&mut self, data: u8) -> Result<(), VMError>;
 
-impl ProtocolSerial {
-    pub fn write(&mut self, data: u8) -> Result<(), VMError> {
-        let usart = unsafe { USART2::ptr().as_ref() }.unwrap();
-        while usart.isr().read().txe().bit_is_clear() {}
-        // 写入数据（自动清 TXE）
-        usart.tdr().write(|w| unsafe { w.tdr().bits(data as u16) });
+    fn flush(&mut self) -> Result<(), VMError>;
+
+    fn read(&mut self) -> Result<u8, VMError>;
+
+    //电脑发来的数据是否已经到了, 不阻塞, 运行期间用来检查电脑有没有发命令
+    fn pending(&mut self) -> bool;
+}
+
+//在电脑上检查协议用的串口, 扮演电脑一方
+//记录单片机发出的每一帧, 并按顺序回复预先设置的数据
+//也可以扮演发命令的电脑: 单片机读的时候依次给出push_command的命令, 再收下单片机的回复
+//clone出来的MockSerial共享同一份状态, 交给Machine之后仍然可以检查
+#[cfg(test)]
+#[derive(Clone, Default)]
+pub struct MockSerial {
+    state: Rc<RefCell<MockState>>,
+}
+
+#[cfg(test)]
+#[derive(Default)]
+struct MockState {
+    //收到的帧: (命令编号, 去掉转义后的数据)
+    frames: Vec<(u8, Vec<u8>)>,
+    //正在接收的帧, 收到0xaa并回复0x55之后开始
+    receiving: Option<Vec<u8>>,
+    escape: bool,
+    //还没有发出的回复, 每个元素是一帧的数据
+    replies: VecDeque<Vec<u8>>,
+    //正在发出的回复, 已经转义并加上了FRAME_END
+    sending: VecDeque<u8>,
+    last_written: u8,
+    //还没有发给单片机的命令, 每个元素是一帧, 包括开头的0xaa和结尾的FRAME_END
+    commands: VecDeque<Vec<u8>>,
+    //正在发给单片机的命令
+    command: VecDeque<u8>,
+    //运行期间电脑插进来的命令, 单片机pending之后读走, 不需要回复
+    interrupt: VecDeque<u8>,
+    //命令已经发完, 等单片机回复
+    awaiting_reply: bool,
+    //正在接收的回复
+    replying: Option<Vec<u8>>,
+    //单片机对每条命令的回复, 去掉了转义
+    command_replies: Vec<Vec<u8>>,
+}
+
+#[cfg(test)]
+impl MockSerial {
+    pub fn new() -> MockSerial {
+        MockSerial::default()
+    }
+
+    //收到下一个需要回复的命令后发出data
+    pub fn push_reply(&self, data: &[u8]) {
+        self.state.borrow_mut().replies.push_back(data.to_vec());
+    }
+
+    pub fn frames(&self) -> Vec<(u8, Vec<u8>)> {
+        self.state.borrow().frames.clone()
+    }
+
+    pub fn take_frames(&self) -> Vec<(u8, Vec<u8>)> {
+        core::mem::take(&mut self.state.borrow_mut().frames)
+    }
+
+    //电脑发给单片机的命令, 单片机处理后的回复见command_replies
+    pub fn push_command(&self, command: &Command) {
+        let frame = MockSerial::frame(command);
+        self.state.borrow_mut().commands.push_back(frame);
+    }
+
+    //包括开头的0xaa和结尾的FRAME_END
+    fn frame(command: &Command) -> Vec<u8> {
+        let mut frame = vec![0xaa, command.head()];
+        for byte in command.data() {
+            if byte == ESCAPE_CHAR || byte == FRAME_END {
+                frame.push(ESCAPE_CHAR);
+            }
+            frame.push(byte);
+        }
+        frame.push(FRAME_END);
+        frame
+    }
+
+    //在单片机执行命令(比如Continue)期间发出command, 见pending
+    pub fn push_interrupt(&self, command: &Command) {
+        let frame = MockSerial::frame(command);
+        self.state.borrow_mut().interrupt.extend(frame);
+    }
+
+    pub fn command_replies(&self) -> Vec<Vec<u8>> {
+        self.state.borrow().command_replies.clone()
+    }
+
+    //还没有被读走的回复数
+    pub fn pending_replies(&self) -> usize {
+        let state = self.state.borrow();
+        state.replies.len() + !state.sending.is_empty() as usize
+    }
+}
+
+#[cfg(test)]
+impl Serial for MockSerial {
+    fn write(&mut self, data: u8) -> Result<(), VMError> {
+        let mut state = self.state.borrow_mut();
+        if state.replying.is_some() {
+            let escape = state.escape;
+            let reply = state.replying.as_mut().unwrap();
+            if !escape && data == ESCAPE_CHAR {
+                state.escape = true;
+            } else if !escape && data == FRAME_END {
+                let reply = state.replying.take().unwrap();
+                state.command_replies.push(reply);
+                state.awaiting_reply = false;
+            } else {
+                reply.push(data);
+                state.escape = false;
+            }
+            return Ok(());
+        }
+        state.last_written = data;
+        let escape = state.escape;
+        let Some(frame) = state.receiving.as_mut() else {
+            return Ok(());
+        };
+        if !escape && data == ESCAPE_CHAR {
+            state.escape = true;
+        } else if !escape && data == FRAME_END {
+            let frame = state.receiving.take().unwrap();
+            if let Some((&head, data)) = frame.split_first() {
+                state.frames.push((head, data.to_vec()));
+            }
+        } else {
+            frame.push(data);
+            state.escape = false;
+        }
         Ok(())
     }
 
-    pub fn flush(&mut self) -> Result<(), VMError> {
-        let usart = unsafe { USART2::ptr().as_ref() }.unwrap();
-        // 等待 TC 标志
-        while usart.isr().read().tc().bit_is_clear() {}
+    fn flush(&mut self) -> Result<(), VMError> {
         Ok(())
     }
 
-    pub fn read(&mut self) -> Result<u8, VMError> {
-        let usart = unsafe { USART2::ptr().as_ref() }.unwrap();
-        // 等待接收完成（RXNE = 1）
-        while usart.isr().read().rxne().bit_is_clear() {}
-        Ok(usart.rdr().read().rdr().bits() as u8)
+    fn read(&mut self) -> Result<u8, VMError> {
+        let mut state = self.state.borrow_mut();
+        //每个0xaa和0xa5只对应一次读
+        match core::mem::take(&mut state.last_written) {
+            0xaa => {
+                state.receiving = Some(Vec::new());
+                state.escape = false;
+                Ok(0x55)
+            }
+            0xa5 => {
+                if state.sending.is_empty() {
+                    let reply = state.replies.pop_front().ok_or(VMError::NonBlockError)?;
+                    for byte in reply {
+                        if byte == ESCAPE_CHAR || byte == FRAME_END {
+                            state.sending.push_back(ESCAPE_CHAR);
+                        }
+                        state.sending.push_back(byte);
+                    }
+                    state.sending.push_back(FRAME_END);
+                }
+                Ok(state.sending.pop_front().unwrap())
+            }
+            _ if !state.awaiting_reply
+                && (!state.command.is_empty() || !state.commands.is_empty()) =>
+            {
+                if state.command.is_empty() {
+                    let command = state.commands.pop_front().unwrap();
+                    state.command = command.into();
+                }
+                let byte = state.command.pop_front().unwrap();
+                state.awaiting_reply = state.command.is_empty();
+                Ok(byte)
+            }
+            _ if !state.interrupt.is_empty() => Ok(state.interrupt.pop_front().unwrap()),
+            //命令发完了, 每发一个0xa5取回复的一个字节
+            _ if state.awaiting_reply => {
+                if state.replying.is_none() {
+                    state.replying = Some(Vec::new());
+                    state.escape = false;
+                }
+                Ok(0xa5)
+            }
+            _ => Err(VMError::NonBlockError),
+        }
+    }
+
+    fn pending(&mut self) -> bool {
+        !self.state.borrow().interrupt.is_empty()
     }
 }
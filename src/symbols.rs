@@ -0,0 +1,52 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::machine::Machine;
+
+//电脑注册的符号, 用于在跟踪和反汇编中把地址显示成函数名+偏移
+#[derive(Debug, Clone, PartialEq)]
+pub struct Symbol {
+    pub address: u32,
+    pub name: String,
+}
+
+impl Machine {
+    //symbols按地址排序, 同一个地址只保留最后注册的名字
+    pub fn add_symbol(&mut self, address: u32, name: String) {
+        match self
+            .symbols
+            .binary_search_by_key(&address, |symbol| symbol.address)
+        {
+            Ok(index) => self.symbols[index].name = name,
+            Err(index) => self.symbols.insert(index, Symbol { address, name }),
+        }
+    }
+
+    pub fn clear_symbols(&mut self) {
+        self.symbols = Vec::new();
+    }
+
+    //地址不超过address的最近的符号, 和address相对它的偏移
+    pub fn symbolize(&self, address: u32) -> Option<(&str, u32)> {
+        let index = match self
+            .symbols
+            .binary_search_by_key(&address, |symbol| symbol.address)
+        {
+            Ok(index) => index,
+            Err(0) => return None,
+            Err(index) => index - 1,
+        };
+        let symbol = &self.symbols[index];
+        Some((&symbol.name, address - symbol.address))
+    }
+
+    //例如"0x00000104 <main+0x4>", 没有符号时只有地址
+    pub fn annotate(&self, address: u32) -> String {
+        match self.symbolize(address) {
+            Some((name, 0)) => format!("{:#010x} <{}>", address, name),
+            Some((name, offset)) => format!("{:#010x} <{}+{:#x}>", address, name, offset),
+            None => format!("{:#010x}", address),
+        }
+    }
+}
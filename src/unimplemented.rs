@@ -0,0 +1,121 @@
+use yaxpeax_arm::armv7::Opcode;
+
+use crate::{machine::Machine, vmerror::VMError};
+
+//还没有实现的指令按用途分类, 每一类可以单独决定遇到时怎么处理
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OpcodeCategory {
+    //CDP, LDC, STC, MCR, MRC这类协处理器指令
+    Coprocessor,
+    //PLD, PLI这类提示, 不执行也不影响结果
+    Hint,
+    //CPS, ERET这类和模式, 异常, 状态切换有关的指令
+    System,
+    //SMAL, SMLAL_halfword
+    Multiply,
+    //其他没有归类的
+    Other,
+}
+
+pub const OPCODE_CATEGORIES: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UnimplementedAction {
+    //直接panic, 方便开发时发现
+    Panic,
+    //返回VMError::Unimplemented, 交给on_error处理, 这是默认的处理方式
+    Trap,
+    //当作NOP跳过
+    Nop,
+}
+
+pub fn opcode_category(opcode: Opcode) -> OpcodeCategory {
+    match opcode {
+        Opcode::CDP2(..)
+        | Opcode::LDC(..)
+        | Opcode::LDC2(..)
+        | Opcode::LDC2L(..)
+        | Opcode::LDCL(..)
+        | Opcode::STC(..)
+        | Opcode::STC2(..)
+        | Opcode::STC2L(..)
+        | Opcode::STCL(..)
+        | Opcode::MCR2(..)
+        | Opcode::MCRR(..)
+        | Opcode::MCRR2(..)
+        | Opcode::MRC2(..)
+        | Opcode::MRRC(..)
+        | Opcode::MRRC2(..) => OpcodeCategory::Coprocessor,
+        Opcode::PLD | Opcode::PLI => OpcodeCategory::Hint,
+        Opcode::BXJ
+        | Opcode::ENTERX
+        | Opcode::CPS(..)
+        | Opcode::CPS_modeonly
+        | Opcode::ERET
+        | Opcode::HVC
+        | Opcode::RFE(..)
+        | Opcode::SMC
+        | Opcode::SRS(..) => OpcodeCategory::System,
+        Opcode::SMAL(..) | Opcode::SMLAL_halfword(..) => OpcodeCategory::Multiply,
+        _ => OpcodeCategory::Other,
+    }
+}
+
+impl Machine {
+    pub fn set_unimplemented_action(
+        &mut self,
+        category: OpcodeCategory,
+        action: UnimplementedAction,
+    ) {
+        self.unimplemented_policy[category as usize] = action;
+    }
+
+    pub fn unimplemented_action(&self, category: OpcodeCategory) -> UnimplementedAction {
+        self.unimplemented_policy[category as usize]
+    }
+
+    //执行到还没有实现的指令时调用, 按unimplemented_policy处理, 默认返回错误
+    pub fn unimplemented(&self, opcode: Opcode) -> Result<(), VMError> {
+        match self.unimplemented_action(opcode_category(opcode)) {
+            UnimplementedAction::Panic => unimplemented!("{:?}", opcode),
+            UnimplementedAction::Trap => Err(VMError::Unimplemented),
+            //PC已经指向下一条指令了
+            UnimplementedAction::Nop => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::machine::tests::arm_machine;
+
+    #[test]
+    fn default_policy_traps() {
+        let machine = arm_machine(&[]);
+        assert!(matches!(
+            machine.unimplemented(Opcode::PLD),
+            Err(VMError::Unimplemented)
+        ));
+    }
+
+    #[test]
+    fn nop_category_returns_ok() {
+        let mut machine = arm_machine(&[]);
+        machine.set_unimplemented_action(OpcodeCategory::Hint, UnimplementedAction::Nop);
+        assert!(machine.unimplemented(Opcode::PLD).is_ok());
+        //其他类别不受影响
+        assert!(matches!(
+            machine.unimplemented(Opcode::ERET),
+            Err(VMError::Unimplemented)
+        ));
+    }
+
+    #[test]
+    #[should_panic]
+    fn panic_category_panics() {
+        let mut machine = arm_machine(&[]);
+        machine.set_unimplemented_action(OpcodeCategory::System, UnimplementedAction::Panic);
+        let _ = machine.unimplemented(Opcode::ERET);
+    }
+}
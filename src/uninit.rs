@@ -0,0 +1,97 @@
+use alloc::vec;
+
+use crate::{machine::Machine, memory::INTERNAL_SIZE, vmerror::VMError};
+
+//内部内存每个字节1位, 100KiB内存需要12800字节, 所以默认不开启
+pub const INIT_MAP_SIZE: usize = INTERNAL_SIZE / 8;
+
+//读到了从来没有写过的内部内存, 参数是读的地址
+//返回Ok时照常读出内存里原有的值, 返回Err时这次访问出错; 没有设置时直接报UninitializedRead
+pub type UninitReadHook = fn(&Machine, u32) -> Result<(), VMError>;
+
+impl Machine {
+    //清空之前的记录, 之后写入的字节才算初始化过, 要在加载程序之前调用
+    pub fn enable_uninit_detection(&mut self) {
+        self.initialized = Some(vec![0; INIT_MAP_SIZE]);
+    }
+
+    pub fn disable_uninit_detection(&mut self) {
+        self.initialized = None;
+    }
+
+    //由write_memory在写内部内存时调用
+    pub fn mark_initialized(&mut self, address: u32) {
+        let address = address as usize;
+        if let Some(initialized) = &mut self.initialized
+            && address < INTERNAL_SIZE
+        {
+            initialized[address / 8] |= 1 << (address % 8);
+        }
+    }
+
+    //没有开启检测时所有内存都算初始化过, 外部内存不检查
+    pub fn is_initialized(&self, address: u32) -> bool {
+        let address = address as usize;
+        match &self.initialized {
+            Some(initialized) if address < INTERNAL_SIZE => {
+                initialized[address / 8] >> (address % 8) & 1 == 1
+            }
+            _ => true,
+        }
+    }
+
+    //由read_memory在读内部内存前调用, 取指令也会经过这里
+    pub fn check_initialized(&self, address: u32) -> Result<(), VMError> {
+        if self.is_initialized(address) {
+            return Ok(());
+        }
+        match self.uninit_read_hook {
+            Some(hook) => hook(self, address),
+            None => Err(VMError::UninitializedRead),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::sync::atomic::{AtomicU32, Ordering};
+
+    use super::*;
+    use crate::machine::tests::arm_machine;
+
+    #[test]
+    fn hook_fires_only_before_first_write() {
+        static HITS: AtomicU32 = AtomicU32::new(0);
+        let mut machine = arm_machine(&[
+            0xe5901000, //ldr r1, [r0]
+            0xe5802000, //str r2, [r0]
+            0xe5903000, //ldr r3, [r0]
+        ]);
+        machine.enable_uninit_detection();
+        //程序本身算初始化过
+        for address in 0..12 {
+            machine.mark_initialized(address);
+        }
+        machine.uninit_read_hook = Some(|_, address| {
+            assert_eq!(address & !3, 0x100);
+            HITS.fetch_add(1, Ordering::Relaxed);
+            Ok(())
+        });
+        machine.memory.data[0x100] = 0x5a;
+        machine.cpu.regs[0] = 0x100;
+        machine.cpu.regs[2] = 0x12345678;
+        machine.step().unwrap();
+        assert_eq!(machine.cpu.regs[1], 0x5a);
+        let hits = HITS.load(Ordering::Relaxed);
+        assert!(hits > 0);
+        machine.step().unwrap();
+        machine.step().unwrap();
+        assert_eq!(machine.cpu.regs[3], 0x12345678);
+        assert_eq!(HITS.load(Ordering::Relaxed), hits);
+
+        machine.uninit_read_hook = None;
+        machine.cpu.regs[0] = 0x200;
+        machine.cpu.regs[15] = 0;
+        assert!(matches!(machine.step(), Err(VMError::UninitializedRead)));
+    }
+}
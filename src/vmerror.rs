@@ -1,12 +1,31 @@
 use core::fmt;
 use nb;
 use yaxpeax_arch::ReadError;
+use yaxpeax_arm::armv7::DecodeError;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum VMError {
     BusError,
     FmtError(fmt::Error),
     NonBlockError,
+    InvalidCommand,
+    //收到的帧超过了MAX_FRAME_SIZE
+    FrameTooLong,
+    ReplayMismatch,
+    AlignmentFault,
+    BreakpointTableFull,
+    HookTableFull,
+    MmioTableFull,
+    //注册的MMIO区域和已有的区域重叠
+    MmioOverlap,
+    Unpredictable,
+    UndefinedInstruction,
+    //还没有实现的指令, 见unimplemented_policy
+    Unimplemented,
+    //开启了未初始化读检测, 读到了没有写过的内部内存
+    UninitializedRead,
+    //取指令失败或者解码器不认识这条指令
+    DecodeError(DecodeError),
 }
 
 impl From<fmt::Error> for VMError {
@@ -33,6 +52,39 @@ impl VMError {
             VMError::BusError => "Bus Error",
             VMError::FmtError(_) => "Serial Error",
             VMError::NonBlockError => "Non Blocking Error",
+            VMError::InvalidCommand => "Invalid Command",
+            VMError::FrameTooLong => "Frame Too Long",
+            VMError::ReplayMismatch => "Replay Mismatch",
+            VMError::AlignmentFault => "Alignment Fault",
+            VMError::BreakpointTableFull => "Breakpoint Table Full",
+            VMError::HookTableFull => "Hook Table Full",
+            VMError::MmioTableFull => "MMIO Table Full",
+            VMError::MmioOverlap => "MMIO Overlap",
+            VMError::Unpredictable => "Unpredictable",
+            VMError::UndefinedInstruction => "Undefined Instruction",
+            VMError::Unimplemented => "Unimplemented",
+            VMError::UninitializedRead => "Uninitialized Read",
+            VMError::DecodeError(_) => "Decode Error",
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fault_strings() {
+        assert_eq!(VMError::AlignmentFault.to_str(), "Alignment Fault");
+        assert_eq!(
+            VMError::UndefinedInstruction.to_str(),
+            "Undefined Instruction"
+        );
+        assert_eq!(VMError::Unpredictable.to_str(), "Unpredictable");
+        //解码器的错误通过ReadError::IOError带上同样的字符串
+        assert!(matches!(
+            ReadError::from(VMError::AlignmentFault),
+            ReadError::IOError("Alignment Fault")
+        ));
+    }
+}
@@ -11,14 +11,21 @@ for i, file_name in enumerate(os.listdir("executor")):
     test_code += "\n\n"
 
 # r9表示测试是否通过
+# 最后写EXIT寄存器结束运行: 通过时退出码为0, 失败时为测试编号+1
 test_code += """
 success:
     mov r9, #1
     nop
+    mov r0, #0x40000000
+    mov r1, #0
+    str r1, [r0]
     b success
 fail:
     mov r9, #0
     nop
+    mov r0, #0x40000000
+    add r1, r8, #1
+    str r1, [r0]
     b fail
 """
 
@@ -0,0 +1,18 @@
+@ Thumb的ADD Rdn, Rm(T2)中Rm为SP时是ADD (SP plus register)
+adr r0, add_sp_thumb + 1
+bx r0
+.syntax unified
+.thumb
+add_sp_thumb:
+    movs r2, #8
+    add r2, sp              @ r2 = sp + 8
+    mov r3, sp
+    adds r3, #8
+    adr r0, add_sp_arm
+    bx r0
+.arm
+.syntax divided
+.align 2
+add_sp_arm:
+    cmp r2, r3
+    bne fail
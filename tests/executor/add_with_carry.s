@@ -0,0 +1,99 @@
+@ 加法保留完整的32位结果, 检查结果和NZCV
+@ 0xffffffff + 1 = 0, Z和C置位
+mvn r0, #0
+adds r1, r0, #1
+mrs r2, APSR
+cmp r1, #0
+bne fail
+and r2, #0xf0000000
+cmp r2, #0x60000000     @ Z C
+bne fail
+@ 0x7fffffff + 1 = 0x80000000, N和V置位
+mvn r0, #0x80000000
+adds r1, r0, #1
+mrs r2, APSR
+cmp r1, #0x80000000
+bne fail
+and r2, #0xf0000000
+cmp r2, #0x90000000     @ N V
+bne fail
+@ 0x80000000 + 0x80000000 = 0, Z C V
+mov r0, #0x80000000
+adds r1, r0, r0
+mrs r2, APSR
+cmp r1, #0
+bne fail
+and r2, #0xf0000000
+cmp r2, #0x70000000
+bne fail
+@ 最高位为1的结果不能丢掉最高位
+mov r0, #0x80000000
+adds r1, r0, #5
+mrs r2, APSR
+orr r3, r0, #5
+cmp r1, r3
+bne fail
+and r2, #0xf0000000
+cmp r2, #0x80000000     @ N
+bne fail
+@ adc带进位: 0xffffffff + 0 + 1 = 0, Z C
+mvn r0, #0
+mov r1, #0
+msr APSR_nzcvq, #0x20000000     @ C=1
+adcs r3, r0, r1
+mrs r2, APSR
+cmp r3, #0
+bne fail
+and r2, #0xf0000000
+cmp r2, #0x60000000
+bne fail
+@ adc带进位: 0x7fffffff + 0 + 1 = 0x80000000, N V
+mvn r0, #0x80000000
+msr APSR_nzcvq, #0x20000000
+adcs r3, r0, r1
+mrs r2, APSR
+cmp r3, #0x80000000
+bne fail
+and r2, #0xf0000000
+cmp r2, #0x90000000
+bne fail
+@ adc带进位: 0xffffffff + 0xffffffff + 1 = 0xffffffff, N C
+mvn r0, #0
+msr APSR_nzcvq, #0x20000000
+adcs r3, r0, r0
+mrs r2, APSR
+cmp r3, r0
+bne fail
+and r2, #0xf0000000
+cmp r2, #0xa0000000
+bne fail
+@ 不带进位时adc和add一样
+msr APSR_nzcvq, #0
+mov r0, #0x40000000
+adcs r3, r0, r0
+mrs r2, APSR
+cmp r3, #0x80000000
+bne fail
+and r2, #0xf0000000
+cmp r2, #0x90000000
+bne fail
+@ 减法: 0 - 1 = 0xffffffff, N, 有借位所以C=0
+mov r0, #0
+subs r1, r0, #1
+mrs r2, APSR
+mvn r3, #0
+cmp r1, r3
+bne fail
+and r2, #0xf0000000
+cmp r2, #0x80000000
+bne fail
+@ 0x80000000 - 1 = 0x7fffffff, C V
+mov r0, #0x80000000
+subs r1, r0, #1
+mrs r2, APSR
+mvn r3, #0x80000000
+cmp r1, r3
+bne fail
+and r2, #0xf0000000
+cmp r2, #0x30000000
+bne fail
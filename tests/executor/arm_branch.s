@@ -0,0 +1,36 @@
+@ 向前和向后的b/bl要正好落在目标上, 目标前后的指令都不能执行
+mov r2, #0
+b arm_branch_forward
+mov r2, #1
+arm_branch_before:
+mov r2, #2              @ 向后跳转的目标前一条
+arm_branch_backward:
+add r2, #10
+b arm_branch_check
+mov r2, #3
+arm_branch_forward:
+add r2, #1
+b arm_branch_backward
+mov r2, #4
+arm_branch_check:
+cmp r2, #11             @ 0 + 1 + 10
+bne fail
+@ bl: 向后跳转并返回到下一条指令
+b arm_branch_call
+arm_branch_func:
+add r2, #1
+bx lr
+mov r2, #5
+arm_branch_call:
+mov r2, #0
+bl arm_branch_func
+bl arm_branch_func2
+cmp r2, #3
+bne fail
+b arm_branch_done
+mov r2, #6
+arm_branch_func2:
+add r2, #2
+bx lr
+mov r2, #7
+arm_branch_done:
@@ -0,0 +1,32 @@
+@ DSB, ISB, DMB, CSDB: 不改变寄存器和标志位
+@ 解码器不能解码Arm的屏障指令, 只在Thumb中测试
+mov r0, #0x60000000
+msr apsr_nzcvq, r0      @ Z,C置1
+mov r1, #0x34
+mov r2, sp
+adr r0, barriers_thumb + 1
+bx r0
+.syntax unified
+.thumb
+.align 2
+barriers_thumb:
+    dsb
+    isb
+    dmb
+    .inst.w 0xf3af8014  @ csdb
+    dsb ish
+    dmb ishst
+    adr r0, barriers_arm
+    bx r0
+.arm
+.syntax divided
+.align 2
+barriers_arm:
+    mrs r3, apsr
+    and r3, #0xf0000000
+    cmp r3, #0x60000000
+    bne fail
+    cmp r1, #0x34
+    bne fail
+    cmp r2, sp
+    bne fail
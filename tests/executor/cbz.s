@@ -0,0 +1,20 @@
+adr r0, cbz_thumb + 1
+bx r0
+.syntax unified
+.thumb
+cbz_thumb:
+    movs r1, #0
+    movs r2, #0             @ r2=1表示cbz跳转了
+    cbz r1, cbz_taken
+    b cbz_back
+cbz_taken:
+    movs r2, #1
+cbz_back:
+    adr r0, cbz_arm
+    bx r0
+.arm
+.syntax divided
+.align 2
+cbz_arm:
+    cmp r2, #1
+    bne fail
@@ -1,4 +1,52 @@
 mov r0, #0x0f0f
 clz r0, r0
 cmp r0, #20
-bne fail
\ No newline at end of file
+bne fail
+@ 全0时结果为32
+mov r1, #0
+clz r0, r1
+cmp r0, #32
+bne fail
+mov r1, #0x80000000
+clz r0, r1
+cmp r0, #0
+bne fail
+mov r1, #0x10000
+clz r0, r1
+cmp r0, #15
+bne fail
+mov r1, #1
+clz r0, r1
+cmp r0, #31
+bne fail
+@ Thumb
+mov r4, #0
+adr r0, clz_thumb + 1
+bx r0
+.syntax unified
+.thumb
+.align 2
+clz_thumb:
+    movs r1, #0
+    clz r2, r1
+    cmp r2, #32
+    it ne
+    movne r4, #1
+    mov r1, #0x80000000
+    clz r2, r1
+    cmp r2, #0
+    it ne
+    movne r4, #2
+    mov r1, #0x10000
+    clz r2, r1
+    cmp r2, #15
+    it ne
+    movne r4, #3
+    adr r0, clz_arm
+    bx r0
+.arm
+.syntax divided
+.align 2
+clz_arm:
+    cmp r4, #0
+    bne fail
@@ -0,0 +1,63 @@
+@ cmn: 0x7fffffff+1, 有符号溢出, 没有进位
+mvn r0, #0x80000000
+cmn r0, #1
+bvc fail
+bcs fail
+bpl fail
+beq fail
+@ cmn: 0xffffffff+1, 进位, 结果为0, 没有溢出
+mvn r0, #0
+cmn r0, #1
+bcc fail
+bvs fail
+bne fail
+bmi fail
+@ cmn: 0x80000000+0x80000000, 进位且溢出
+mov r0, #0x80000000
+mov r1, #0x80000000
+cmn r0, r1
+bcc fail
+bvc fail
+bne fail
+@ cmn: -1+(-1) = -2, 进位, 没有溢出
+mvn r0, #0
+mvn r1, #0
+cmn r0, r1
+bcc fail
+bvs fail
+bpl fail
+@ cmn: 和负常数比较, r0 = -5时cmn r0, #5相等
+mvn r0, #4
+cmn r0, #5
+bne fail
+@ Thumb: 16位cmn没有rd
+adr r0, cmn_flags_thumb + 1
+bx r0
+.syntax unified
+.thumb
+.align 2
+cmn_flags_thumb:
+    movs r5, #0
+    mov r0, #0x80000000
+    subs r0, #1             @ 0x7fffffff
+    movs r1, #1
+    cmn r0, r1
+    bvc cmn_flags_thumb_bad
+    bcs cmn_flags_thumb_bad
+    movs r0, #0
+    subs r0, #1             @ 0xffffffff
+    cmn.w r0, #1
+    bcc cmn_flags_thumb_bad
+    bne cmn_flags_thumb_bad
+    b cmn_flags_thumb_done
+cmn_flags_thumb_bad:
+    movs r5, #1
+cmn_flags_thumb_done:
+    adr r0, cmn_flags_arm
+    bx r0
+.arm
+.syntax divided
+.align 2
+cmn_flags_arm:
+    cmp r5, #0
+    bne fail
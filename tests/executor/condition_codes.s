@@ -0,0 +1,74 @@
+@ 对NZCV的16种组合检查每个条件码
+@ 每对相反的条件码用一个寄存器: 低16位的第f位表示NZCV=f时前一个条件成立, 高16位表示后一个
+mov r0, #0              @ r0 = NZCV
+mov r1, #0
+mov r2, #0
+mov r3, #0
+mov r4, #0
+mov r5, #0
+mov r6, #0
+mov r7, #0
+condition_codes_loop:
+mov r10, r0, lsl #28
+msr apsr_nzcvq, r10
+mov r11, #1
+mov r11, r11, lsl r0
+mov r12, r11, lsl #16
+orreq r1, r11
+orrne r1, r12
+orrhs r2, r11
+orrlo r2, r12
+orrmi r3, r11
+orrpl r3, r12
+orrvs r4, r11
+orrvc r4, r12
+orrhi r5, r11
+orrls r5, r12
+orrge r6, r11
+orrlt r6, r12
+orrgt r7, r11
+orrle r7, r12
+add r0, #1
+cmp r0, #16
+bne condition_codes_loop
+mov r9, #0x0f000000
+orr r9, #0x000f0000
+orr r9, #0xf000
+orr r9, #0xf0
+cmp r1, r9              @ eq/ne
+bne fail
+mov r9, #0x0033000000
+orr r9, #0x00330000
+orr r9, #0xcc00
+orr r9, #0xcc
+cmp r2, r9              @ hs/lo
+bne fail
+mov r9, #0x00000000
+orr r9, #0x00ff0000
+orr r9, #0xff00
+cmp r3, r9              @ mi/pl
+bne fail
+mov r9, #0x0055000000
+orr r9, #0x00550000
+orr r9, #0xaa00
+orr r9, #0xaa
+cmp r4, r9              @ vs/vc
+bne fail
+mov r9, #0x00f3000000
+orr r9, #0x00f30000
+orr r9, #0x0c00
+orr r9, #0x0c
+cmp r5, r9              @ hi/ls
+bne fail
+mov r9, #0x0055000000
+orr r9, #0x00aa0000
+orr r9, #0xaa00
+orr r9, #0x55
+cmp r6, r9              @ ge/lt
+bne fail
+mov r9, #0xf5000000
+orr r9, #0x00fa0000
+orr r9, #0x0a00
+orr r9, #0x05
+cmp r7, r9              @ gt/le
+bne fail
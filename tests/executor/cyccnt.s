@@ -0,0 +1,10 @@
+mov r4, #0x40000000
+ldr r0, [r4, #4]        @ CYCCNT
+mov r1, #3
+cyccnt_loop:
+    subs r1, r1, #1
+    bne cyccnt_loop
+ldr r2, [r4, #4]
+sub r2, r2, r0
+cmp r2, #12             @ 8条指令, 加上2次跳转每次2个周期
+bne fail
@@ -0,0 +1,49 @@
+@ DBG和没有分配编号的HINT: r0~r7, sp和标志位都不变
+mov r0, #0x90000000
+msr apsr_nzcvq, r0      @ N,V置1
+mov r0, #1
+mov r1, #2
+mov r2, #3
+mov r3, #4
+mov r4, #5
+mov r5, #6
+mov r6, #7
+mov r7, sp
+dbg #5
+hint #5
+hint #0x7f
+adr r12, dbg_hint_thumb + 1
+bx r12
+.syntax unified
+.thumb
+.align 2
+dbg_hint_thumb:
+    dbg #5
+    hint #5
+    hint #15
+    adr r12, dbg_hint_arm
+    bx r12
+.arm
+.syntax divided
+.align 2
+dbg_hint_arm:
+    mrs r12, apsr
+    and r12, #0xf0000000
+    cmp r12, #0x90000000
+    bne fail
+    cmp r0, #1
+    bne fail
+    cmp r1, #2
+    bne fail
+    cmp r2, #3
+    bne fail
+    cmp r3, #4
+    bne fail
+    cmp r4, #5
+    bne fail
+    cmp r5, #6
+    bne fail
+    cmp r6, #7
+    bne fail
+    cmp r7, sp
+    bne fail
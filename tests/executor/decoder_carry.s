@@ -0,0 +1,39 @@
+@ 解码器用当前的C展开立即数, 上一条指令改变的C要影响下一条指令的解码
+@ adds设置C, 紧接着没有循环移位的movs保持C
+mov r0, #0
+msr apsr_nzcvq, r0
+mvn r0, #0
+adds r0, #1             @ C = 1
+movs r1, #0x55
+bcc fail
+@ subs清除C, 紧接着的movs保持C
+mov r0, #0
+subs r0, #1             @ 借位, C = 0
+movs r1, #0x55
+bcs fail
+@ Thumb: 32位movs的ThumbExpandImm
+adr r0, decoder_carry_thumb + 1
+bx r0
+.syntax unified
+.thumb
+decoder_carry_thumb:
+    movs r0, #0
+    subs r0, #1             @ r0 = 0xffffffff, C = 0
+    adds r0, #1             @ C = 1
+    movs.w r1, #0x55        @ C不变
+    mov r3, #0
+    adc r3, r3, #0          @ r3 = C
+    subs r0, #1             @ C = 0
+    movs.w r1, #0x55
+    mov r4, #0
+    adc r4, r4, #0          @ r4 = C
+    adr r0, decoder_carry_arm
+    bx r0
+.arm
+.syntax divided
+.align 2
+decoder_carry_arm:
+    cmp r3, #1
+    bne fail
+    cmp r4, #0
+    bne fail
@@ -0,0 +1,19 @@
+mov r4, #0x40000000
+ldr r0, [r4, #0x100]    @ 签名"VMST"
+mov r1, #0x54000000
+orr r1, #0x00530000
+orr r1, #0x4d00
+orr r1, #0x56
+cmp r0, r1
+bne fail
+str r4, [r4, #0x100]    @ 只读, 写入忽略
+ldr r0, [r4, #0x100]
+cmp r0, r1
+bne fail
+ldr r0, [r4, #0x108]    @ arch_version
+cmp r0, #7
+bne fail
+ldr r0, [r4, #0x10c]    @ 功能
+and r0, #0xff
+cmp r0, #0xff           @ Arm, Thumb, Thumb-2, 乘法, 并行加减, 饱和运算, 除法, 独占访问
+bne fail
@@ -0,0 +1,58 @@
+@ 带加法的扩展指令: 按ROR 0/8/16/24旋转, 且不修改N,Z,C,V,Q,GE
+mov r0, #0xf8000000
+orr r0, #0x000f0000
+mov r1, #0x11000000
+orr r1, #0x00220000
+orr r1, #0x3300
+orr r1, #0x44           @ r1 = 0x11223344
+mov r5, #0x1000
+@ sxtab: ror #8取到0x33
+sxtab r2, r5, r1, ror #8
+mov r3, #0x1000
+orr r3, #0x33
+cmp r2, r3
+bne fail
+@ uxtab: ror #24取到0x11
+uxtab r2, r5, r1, ror #24
+mov r3, #0x1000
+orr r3, #0x11
+cmp r2, r3
+bne fail
+@ sxtah: ror #8取到0x2233
+sxtah r2, r5, r1, ror #8
+mov r3, #0x3200
+orr r3, #0x33
+cmp r2, r3
+bne fail
+@ uxtah: ror #24取到0x4411
+uxtah r2, r5, r1, ror #24
+mov r3, #0x5400
+orr r3, #0x11
+cmp r2, r3
+bne fail
+@ sxtab16: ror #16后两个字节是0x22和0x44
+sxtab16 r2, r5, r1, ror #16
+mov r3, #0x00440000
+orr r3, #0x1000
+orr r3, #0x22
+cmp r2, r3
+bne fail
+@ uxtab16: ror #8后两个字节是0x33和0x11
+uxtab16 r2, r5, r1, ror #8
+mov r3, #0x00110000
+orr r3, #0x1000
+orr r3, #0x33
+cmp r2, r3
+bne fail
+@ 检查标志位没有被修改
+msr apsr_nzcvqg, r0     @ N,Z,C,V,Q和GE全部置1
+sxtab r2, r5, r1
+uxtab r2, r5, r1
+sxtah r2, r5, r1
+uxtah r2, r5, r1
+sxtab16 r2, r5, r1
+uxtab16 r2, r5, r1
+mrs r4, apsr
+and r4, r0
+cmp r4, r0
+bne fail
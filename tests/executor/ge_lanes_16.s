@@ -0,0 +1,38 @@
+@ 半字并行加减: m的两个半字不同, 检查交叉的是m的哪个半字
+mov r0, #0x50000
+orr r0, #0x3            @ r0 = 0x00050003
+mov r1, #0x10000
+orr r1, #0x2            @ r1 = 0x00010002
+sasx r2, r0, r1         @ 高半字5+2, 低半字3-1
+mov r4, #0x70000
+orr r4, #0x2
+cmp r2, r4              @ 0x00070002
+bne fail
+ssax r2, r0, r1         @ 高半字5-2, 低半字3+1
+mov r4, #0x30000
+orr r4, #0x4
+cmp r2, r4              @ 0x00030004
+bne fail
+ssub16 r2, r0, r1       @ 高半字5-1, 低半字3-2
+mov r4, #0x40000
+orr r4, #0x1
+cmp r2, r4              @ 0x00040001
+bne fail
+@ 有符号: 结果为0时GE置位, 为-1时清零
+mov r0, #0
+mov r1, #0x10000        @ r1 = 0x00010000
+ssub16 r2, r0, r1       @ 高半字0-1, 低半字0-0
+mrs r3, APSR
+and r3, #0x000f0000
+cmp r3, #0x30000        @ GE = 0b0011
+bne fail
+@ 无符号: 0xffff+1进位时GE置位, 0+1不进位时清零
+mov r0, #0xff000000
+orr r0, #0x00ff0000     @ r0 = 0xffff0000
+mov r1, #0x10000
+orr r1, #0x1            @ r1 = 0x00010001
+uadd16 r2, r0, r1
+mrs r3, APSR
+and r3, #0x000f0000
+cmp r3, #0xc0000        @ GE = 0b1100
+bne fail
@@ -0,0 +1,88 @@
+@ 每个字节通道的GE位和结果都和逐通道计算的参考值比较
+@ 有符号看结果是否>=0, UADD8看是否进位, USUB8看是否借位
+
+mov r0, #0x80000000
+orr r0, #0x7f0000
+orr r0, #0xff00
+orr r0, #0x7f           @ r0 = 0x807fff7f
+mov r1, #0x80000000
+orr r1, #0x800000
+orr r1, #0x100
+orr r1, #0x1            @ r1 = 0x80800101
+sadd8 r2, r0, r1
+mrs r3, APSR
+and r3, #0x000f0000
+cmp r3, #0x30000        @ GE = 0b0011
+bne fail
+mov r4, #0xff0000
+orr r4, #0x80           @ r4 = 0x00ff0080
+cmp r2, r4
+bne fail
+ssub8 r2, r0, r1
+mrs r3, APSR
+and r3, #0x000f0000
+cmp r3, #0xd0000        @ GE = 0b1101
+bne fail
+mov r4, #0xff0000
+orr r4, #0xfe00
+orr r4, #0x7e           @ r4 = 0x00fffe7e
+cmp r2, r4
+bne fail
+uadd8 r2, r0, r1
+mrs r3, APSR
+and r3, #0x000f0000
+cmp r3, #0xa0000        @ GE = 0b1010
+bne fail
+mov r4, #0xff0000
+orr r4, #0x80           @ r4 = 0x00ff0080
+cmp r2, r4
+bne fail
+usub8 r2, r0, r1
+mrs r3, APSR
+and r3, #0x000f0000
+cmp r3, #0xb0000        @ GE = 0b1011
+bne fail
+mov r4, #0xff0000
+orr r4, #0xfe00
+orr r4, #0x7e           @ r4 = 0x00fffe7e
+cmp r2, r4
+bne fail
+
+mov r0, #0x400000
+orr r0, #0x100
+orr r0, #0x80           @ r0 = 0x00400180
+mov r1, #0x400000
+orr r1, #0xff00
+orr r1, #0x80           @ r1 = 0x0040ff80
+sadd8 r2, r0, r1
+mrs r3, APSR
+and r3, #0x000f0000
+cmp r3, #0xe0000        @ GE = 0b1110
+bne fail
+mov r4, #0x800000       @ r4 = 0x00800000
+cmp r2, r4
+bne fail
+ssub8 r2, r0, r1
+mrs r3, APSR
+and r3, #0x000f0000
+cmp r3, #0xf0000        @ GE = 0b1111
+bne fail
+mov r4, #0x200          @ r4 = 0x00000200
+cmp r2, r4
+bne fail
+uadd8 r2, r0, r1
+mrs r3, APSR
+and r3, #0x000f0000
+cmp r3, #0x30000        @ GE = 0b0011
+bne fail
+mov r4, #0x800000       @ r4 = 0x00800000
+cmp r2, r4
+bne fail
+usub8 r2, r0, r1
+mrs r3, APSR
+and r3, #0x000f0000
+cmp r3, #0xd0000        @ GE = 0b1101
+bne fail
+mov r4, #0x200          @ r4 = 0x00000200
+cmp r2, r4
+bne fail
@@ -0,0 +1,83 @@
+@ 半加半减: SH*按有符号数, UH*按无符号数, 通道为负数时两者的结果不同
+@ shadd8/uhadd8: n = (4, 2, -1, -128), m = (2, 2, 1, -128)
+mov r0, #0x80000000
+orr r0, #0xff0000
+orr r0, #0x200
+orr r0, #4              @ r0 = 0x80ff0204
+mov r1, #0x80000000
+orr r1, #0x10000
+orr r1, #0x200
+orr r1, #2              @ r1 = 0x80010202
+shadd8 r2, r0, r1
+mov r4, #0x80000000
+orr r4, #0x200
+orr r4, #3
+cmp r2, r4              @ 0x80000203 (3, 2, 0, -128)
+bne fail
+uhadd8 r2, r0, r1
+orr r4, #0x800000
+cmp r2, r4              @ 0x80800203 (3, 2, 128, 128)
+bne fail
+@ shadd16/uhadd16: 高半字0xffff+0x0001
+mov r0, #0xff000000
+orr r0, #0xff0000
+orr r0, #2              @ r0 = 0xffff0002
+mov r1, #0x10000
+orr r1, #4              @ r1 = 0x00010004
+shadd16 r2, r0, r1
+cmp r2, #3              @ (-1+1)/2 = 0
+bne fail
+uhadd16 r2, r0, r1
+mov r4, #0x80000000
+orr r4, #3
+cmp r2, r4              @ 0x10000/2 = 0x8000
+bne fail
+@ shsub16/uhsub16: 低半字0x10-0x20, 高半字0xfffe-2
+mov r0, #0xff000000
+orr r0, #0xfe0000
+orr r0, #0x10           @ r0 = 0xfffe0010
+mov r1, #0x20000
+orr r1, #0x20           @ r1 = 0x00020020
+shsub16 r2, r0, r1
+mvn r4, #0x10000
+bic r4, #7
+cmp r2, r4              @ 0xfffefff8 (-8, -2)
+bne fail
+uhsub16 r2, r0, r1
+bic r4, #0x80000000
+cmp r2, r4              @ 0x7ffefff8 (差为负数时最高位也是1, 0x7ffe)
+bne fail
+@ shsub8/uhsub8: 0xfe-2
+mov r0, #0xfe
+mov r1, #2
+shsub8 r2, r0, r1
+cmp r2, #0xfe           @ (-2-2)/2 = -2
+bne fail
+uhsub8 r2, r0, r1
+cmp r2, #0x7e           @ (254-2)/2 = 126
+bne fail
+@ shasx/uhasx: 低半字n.lo-m.hi, 高半字n.hi+m.lo
+mov r0, #0xff000000
+orr r0, #0xfe0000
+orr r0, #6              @ r0 = 0xfffe0006
+mov r1, #0x40000
+orr r1, #2              @ r1 = 0x00040002
+shasx r2, r0, r1
+cmp r2, #1              @ ((-2+2)/2, (6-4)/2)
+bne fail
+uhasx r2, r0, r1
+mov r4, #0x80000000
+orr r4, #1
+cmp r2, r4              @ ((0xfffe+2)/2, 1)
+bne fail
+@ shsax/uhsax: 低半字n.lo+m.hi, 高半字n.hi-m.lo
+shsax r2, r0, r1
+mov r4, #0xff000000
+orr r4, #0xfe0000
+orr r4, #5
+cmp r2, r4              @ 0xfffe0005 ((-2-2)/2, (6+4)/2)
+bne fail
+uhsax r2, r0, r1
+bic r4, #0x80000000
+cmp r2, r4              @ 0x7ffe0005
+bne fail
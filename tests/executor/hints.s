@@ -0,0 +1,57 @@
+@ 提示指令: 都不改变寄存器和标志位
+mov r0, #0xf0000000
+msr apsr_nzcvq, r0      @ 先把N,Z,C,V都置1
+mov r1, #0x12
+mov r2, sp
+nop
+yield
+sev
+wfe                     @ 清除SEV设置的事件, 不等待
+wfe                     @ 没有事件源, 不等待
+wfi
+dbg #5
+hint #5
+hint #20                @ csdb
+mrs r3, apsr
+and r3, #0xf0000000
+cmp r3, #0xf0000000
+bne fail
+cmp r1, #0x12
+bne fail
+cmp r2, sp
+bne fail
+@ Thumb: 16位和32位的提示指令
+mov r3, #0xf0000000
+msr apsr_nzcvq, r3
+adr r0, hints_thumb + 1
+bx r0
+.syntax unified
+.thumb
+.align 2
+hints_thumb:
+    nop
+    yield
+    sev
+    wfe
+    wfi
+    hint #5
+    nop.w
+    yield.w
+    sev.w
+    wfe.w
+    wfi.w
+    dbg #5
+    adr r0, hints_arm
+    bx r0
+.arm
+.syntax divided
+.align 2
+hints_arm:
+    mrs r3, apsr
+    and r3, #0xf0000000
+    cmp r3, #0xf0000000
+    bne fail
+    cmp r1, #0x12
+    bne fail
+    cmp r2, sp
+    bne fail
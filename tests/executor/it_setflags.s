@@ -0,0 +1,44 @@
+@ IT块里16位的ADD不更新标志, 32位的ADDS.W仍然按S位更新标志
+@ IT块中的指令按ITSTATE给出的条件执行
+adr r0, it_setflags_thumb + 1
+bx r0
+.syntax unified
+.thumb
+it_setflags_thumb:
+    movs r1, #1
+    movs r2, #0
+    movs r3, #0
+    movs r4, #0             @ r4记录结果, 1表示16位的ADD没有改变Z
+    movs r5, #0             @ r5记录结果, 1表示32位的ADDS.W清除了Z
+    movs r6, #0             @ Z = 1
+    it eq
+    addeq r2, r2, r1        @ 16位编码, 不更新标志
+    bne it_setflags_16
+    movs r4, #1
+it_setflags_16:
+    movs r6, #0             @ Z = 1
+    it eq
+    addseq.w r3, r3, r1     @ 32位编码, 结果为1, Z被清除
+    beq it_setflags_32
+    movs r5, #1
+it_setflags_32:
+    movs r7, #0
+    movs r6, #0             @ Z = 1
+    it ne
+    addne r7, r7, r1        @ 条件不满足, 不执行
+    adr r0, it_setflags_arm
+    bx r0
+.arm
+.syntax divided
+.align 2
+it_setflags_arm:
+    cmp r2, #1
+    bne fail
+    cmp r3, #1
+    bne fail
+    cmp r4, #1
+    bne fail
+    cmp r5, #1
+    bne fail
+    cmp r7, #0
+    bne fail
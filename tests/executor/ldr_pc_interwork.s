@@ -0,0 +1,17 @@
+@ ARMv5以上LDR PC会根据最低位切换到Thumb
+adr r0, ldr_pc_thumb + 1
+str r0, [sp, #-4]!
+mov r2, #0              @ r2=1表示进入了Thumb
+ldr pc, [sp], #4
+.syntax unified
+.thumb
+ldr_pc_thumb:
+    movs r2, #1
+    adr r0, ldr_pc_arm
+    bx r0
+.arm
+.syntax divided
+.align 2
+ldr_pc_arm:
+    cmp r2, #1
+    bne fail
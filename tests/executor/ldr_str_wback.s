@@ -0,0 +1,93 @@
+@ 不写回的形式不能改变基址寄存器
+sub r1, sp, #64
+mov r3, r1
+mov r0, #0x5a
+str r0, [r1]
+cmp r1, r3
+bne fail
+str r0, [r1, #8]
+cmp r1, r3
+bne fail
+mov r2, #8
+str r0, [r1, r2]
+cmp r1, r3
+bne fail
+mov r0, #0
+ldr r0, [r1]
+cmp r0, #0x5a
+bne fail
+cmp r1, r3
+bne fail
+ldr r0, [r1, #8]
+cmp r1, r3
+bne fail
+ldr r0, [r1, r2, lsl #0]
+cmp r1, r3
+bne fail
+ldrb r0, [r1, #8]
+cmp r1, r3
+bne fail
+ldrh r0, [r1, #-0]
+cmp r1, r3
+bne fail
+@ Rt和Rn相同时只写入读到的值
+mov r4, r1
+ldr r4, [r4]
+cmp r4, #0x5a
+bne fail
+@ 前变址写回
+ldr r0, [r1, #8]!
+cmp r0, #0x5a
+bne fail
+sub r4, r1, r3
+cmp r4, #8
+bne fail
+mov r1, r3
+ldr r0, [r1, r2]!
+sub r4, r1, r3
+cmp r4, #8
+bne fail
+mov r1, r3
+str r0, [r1, #4]!
+sub r4, r1, r3
+cmp r4, #4
+bne fail
+mov r1, r3
+ldrh r0, [r1, #2]!
+sub r4, r1, r3
+cmp r4, #2
+bne fail
+@ 后变址总是写回, 访问的是原来的地址
+mov r1, r3
+ldr r0, [r1], #8
+cmp r0, #0x5a
+bne fail
+sub r4, r1, r3
+cmp r4, #8
+bne fail
+mov r1, r3
+str r0, [r1], #-4
+sub r4, r3, r1
+cmp r4, #4
+bne fail
+@ Thumb: [Rn, #imm]不写回, [Rn, #imm]!写回, r5和r6记录基址的变化
+mov r1, r3
+adr r0, wback_thumb + 1
+bx r0
+.syntax unified
+.thumb
+wback_thumb:
+    ldr r0, [r1, #8]
+    subs r5, r1, r3
+    ldr.w r0, [r1, #8]!
+    subs r6, r1, r3
+    adr r0, wback_arm
+    bx r0
+.arm
+.syntax divided
+.align 2
+wback_arm:
+    cmp r5, #0
+    bne fail
+    cmp r6, #8
+    bne fail
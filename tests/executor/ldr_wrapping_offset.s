@@ -0,0 +1,29 @@
+@ 地址的计算按u32回绕
+mov r6, sp
+sub sp, #8
+mov r0, #0x77
+str r0, [sp]
+@ 基址小于减去的偏移: 4 - (4 - SP) = SP
+mov r1, #4
+rsb r2, sp, #4          @ r2 = 4 - SP, 回绕成很大的数
+ldr r3, [r1, -r2]
+cmp r3, #0x77
+bne fail
+@ 加上负数的偏移: (SP + 16) + (-16)
+add r1, sp, #16
+mvn r2, #15             @ r2 = -16
+ldr r3, [r1, r2]
+cmp r3, #0x77
+bne fail
+@ 后变址写回时回绕
+mov r1, sp
+ldr r3, [r1], r2        @ r1 = SP - 16
+add r4, r1, #16
+cmp r4, sp
+bne fail
+mov r1, #4
+ldr r3, [r1], #-8       @ 读地址4, 然后r1 = 0xfffffffc
+mvn r4, #3
+cmp r1, r4
+bne fail
+mov sp, r6
@@ -0,0 +1,19 @@
+@ LDRD/STRD: 地址只需要4字节对齐, 不需要8字节对齐
+@ 没有对齐时报AlignmentFault, 这里只测试对齐的情况
+mov r4, sp
+sub r4, #20             @ sp是8的倍数, r4+4不是
+mov r0, #0x11
+orr r0, #0x2200
+mov r1, #0x33
+orr r1, #0x4400
+strd r0, r1, [r4, #4]
+mov r2, #0
+mov r3, #0
+ldrd r2, r3, [r4, #4]
+cmp r2, r0
+bne fail
+cmp r3, r1
+bne fail
+ldr r2, [r4, #8]        @ 高地址是第二个寄存器
+cmp r2, r1
+bne fail
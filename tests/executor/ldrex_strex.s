@@ -0,0 +1,110 @@
+@ ldrex之后strex成功, 写入并返回0
+sub r1, sp, #64
+mov r0, #5
+str r0, [r1]
+ldrex r2, [r1]
+cmp r2, #5
+bne fail
+add r2, r2, #1
+strex r3, r2, [r1]
+cmp r3, #0
+bne fail
+ldr r0, [r1]
+cmp r0, #6
+bne fail
+@ 监视器已经被strex清除, 再次strex失败, 不写入
+mov r2, #9
+strex r3, r2, [r1]
+cmp r3, #1
+bne fail
+ldr r0, [r1]
+cmp r0, #6
+bne fail
+@ ldrex之后地址被普通的str改写, strex失败
+ldrex r2, [r1]
+mov r0, #7
+str r0, [r1]
+strex r3, r2, [r1]
+cmp r3, #1
+bne fail
+ldr r0, [r1]
+cmp r0, #7
+bne fail
+@ 写别的地址不影响监视器
+ldrex r2, [r1]
+str r0, [r1, #8]
+strex r3, r2, [r1]
+cmp r3, #0
+bne fail
+@ 地址不一致时strex失败
+ldrex r2, [r1]
+add r4, r1, #4
+strex r3, r2, [r4]
+cmp r3, #1
+bne fail
+@ 字节和半字
+mov r0, #0
+str r0, [r1]
+ldrexb r2, [r1]
+mov r2, #0xab
+strexb r3, r2, [r1]
+cmp r3, #0
+bne fail
+ldrexh r2, [r1]
+cmp r2, #0xab
+bne fail
+orr r2, #0xcd00
+strexh r3, r2, [r1]
+cmp r3, #0
+bne fail
+ldr r0, [r1]
+mov r4, #0xcd00
+orr r4, #0xab
+cmp r0, r4
+bne fail
+@ 大小不一致时strex失败
+ldrexb r2, [r1]
+strex r3, r0, [r1]
+cmp r3, #1
+bne fail
+@ 双字
+mov r4, #0x11
+mov r5, #0x22
+ldrexd r6, r7, [r1]
+strexd r3, r4, r5, [r1]
+cmp r3, #0
+bne fail
+ldrd r6, r7, [r1]
+cmp r6, #0x11
+bne fail
+cmp r7, #0x22
+bne fail
+@ Thumb下同样可用, r5和r6记录strex的结果
+@ 解码器解不出Arm的clrex(和dmb一样), clrex放在Thumb里测, r4记录clrex之后strex的结果
+adr r0, excl_thumb + 1
+bx r0
+.syntax unified
+.thumb
+excl_thumb:
+    ldrex r2, [r1]
+    adds r2, #1
+    strex r5, r2, [r1]
+    strex r6, r2, [r1]
+    ldrex r2, [r1]
+    clrex
+    strex r4, r2, [r1]
+    adr r0, excl_arm
+    bx r0
+.arm
+.syntax divided
+.align 2
+excl_arm:
+    cmp r5, #0
+    bne fail
+    cmp r6, #1
+    bne fail
+    cmp r4, #1
+    bne fail
+    ldr r0, [r1]
+    cmp r0, #0x12
+    bne fail
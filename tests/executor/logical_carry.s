@@ -0,0 +1,62 @@
+@ 逻辑运算的C来自移位器, 没有移位时C不变
+@ ands r0, r1, r2, lsr #1: 移出的是r2的第0位
+mov r1, #0xff
+mov r2, #3
+msr apsr_nzcvq, #0
+ands r0, r1, r2, lsr #1
+bcc fail
+cmp r0, #1
+bne fail
+mov r2, #2
+msr apsr_nzcvq, #0x20000000
+ands r0, r1, r2, lsr #1
+bcs fail
+@ 寄存器移位的移位量
+mov r3, #2
+mov r2, #6
+msr apsr_nzcvq, #0
+ands r0, r1, r2, lsr r3
+bcc fail
+@ orrs/eors/bics/mvns也一样
+mov r2, #1
+msr apsr_nzcvq, #0
+orrs r0, r1, r2, lsr #1
+bcc fail
+msr apsr_nzcvq, #0
+eors r0, r1, r2, lsr #1
+bcc fail
+msr apsr_nzcvq, #0
+bics r0, r1, r2, lsr #1
+bcc fail
+msr apsr_nzcvq, #0
+mvns r0, r2, lsr #1
+bcc fail
+@ Thumb: 16位的ANDS没有移位, C不变; 32位的ANDS.W带移位时C来自移位器
+mov r4, #0
+adr r0, logical_carry_thumb + 1
+bx r0
+.syntax unified
+.thumb
+.align 2
+logical_carry_thumb:
+    movs r1, #0xff
+    movs r2, #3
+    mov r3, #0x20000000
+    msr apsr_nzcvq, r3
+    ands r1, r2
+    it cc
+    movcc r4, #1            @ C被清除了
+    movs r1, #0xff
+    mov r3, #0
+    msr apsr_nzcvq, r3
+    ands.w r0, r1, r2, lsr #1
+    it cc
+    movcc r4, #2            @ C没有从移位器得到
+    adr r0, logical_carry_arm
+    bx r0
+.arm
+.syntax divided
+.align 2
+logical_carry_arm:
+    cmp r4, #0
+    bne fail
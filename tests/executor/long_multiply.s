@@ -0,0 +1,71 @@
+@ 长乘法的操作数都是RdLo, RdHi, Rn, Rm, RdLo和RdHi的初值和Rn, Rm不同, 读错寄存器时结果不同
+@ umull: 0x80000000*6 = 0x3_00000000
+mov r0, #7
+mov r1, #11
+mov r2, #0x80000000
+mov r3, #6
+umull r0, r1, r2, r3
+cmp r0, #0
+bne fail
+cmp r1, #3
+bne fail
+@ smull: -2*3 = -6
+mov r0, #7
+mov r1, #11
+mvn r2, #1
+mov r3, #3
+smull r0, r1, r2, r3
+cmn r0, #6
+bne fail
+cmn r1, #1
+bne fail
+@ umlal: 0x10000*0x10000+0x2_00000001 = 0x3_00000001
+mov r0, #1
+mov r1, #2
+mov r2, #0x10000
+mov r3, #0x10000
+umlal r0, r1, r2, r3
+cmp r0, #1
+bne fail
+cmp r1, #3
+bne fail
+@ smlal: -1*3+10 = 7
+mov r0, #10
+mov r1, #0
+mvn r2, #0
+mov r3, #3
+smlal r0, r1, r2, r3
+cmp r0, #7
+bne fail
+cmp r1, #0
+bne fail
+@ umaal: 3*4+1+2 = 15
+mov r0, #1
+mov r1, #2
+mov r2, #3
+mov r3, #4
+umaal r0, r1, r2, r3
+cmp r0, #15
+bne fail
+cmp r1, #0
+bne fail
+@ smlald: 3*5+2*4+100 = 123
+mov r0, #100
+mov r1, #0
+mov r2, #3
+orr r2, #0x20000
+mov r3, #5
+orr r3, #0x40000
+smlald r0, r1, r2, r3
+cmp r0, #123
+bne fail
+cmp r1, #0
+bne fail
+@ smlsld: 3*5-2*4+100 = 107
+mov r0, #100
+mov r1, #0
+smlsld r0, r1, r2, r3
+cmp r0, #107
+bne fail
+cmp r1, #0
+bne fail
@@ -3,4 +3,55 @@ mov r1, #11
 mov r2, #13
 mla r0, r0, r1,r2
 cmp r0, #90
-bne fail
\ No newline at end of file
+bne fail
+@ 乘积和累加都按32位回绕
+mov r0, #0x10000
+mov r1, #0x10000
+orr r1, #1
+mvn r2, #0              @ 0xffffffff
+mla r3, r0, r1, r2      @ 0x1_0001_0000 + 0xffffffff
+mov r4, #0xff00
+orr r4, #0xff
+cmp r3, r4
+bne fail
+@ mlas设置N和Z, C和V不变
+msr apsr_nzcvq, #0x30000000
+mov r0, #0
+mla r3, r0, r1, r0
+mlas r3, r0, r1, r0
+bne fail
+bcc fail
+bvc fail
+bmi fail
+mov r0, #0x40000000
+mov r1, #2
+mov r2, #0
+msr apsr_nzcvq, #0
+mlas r3, r0, r1, r2     @ 0x80000000
+bpl fail
+beq fail
+bcs fail
+bvs fail
+@ mls: a - n*m, 不影响标志位
+mov r0, #3
+mov r1, #5
+mov r2, #100
+msr apsr_nzcvq, #0xf0000000
+mls r3, r0, r1, r2
+cmp r3, #85
+bne fail
+mov r2, #1
+msr apsr_nzcvq, #0
+mls r3, r0, r1, r2      @ 1 - 15回绕
+mrs r4, apsr
+tst r4, #0xf8000000
+bne fail
+mvn r4, #13             @ -14
+cmp r3, r4
+bne fail
+mov r0, #0x10000
+mov r1, #0x10000
+mov r2, #5
+mls r3, r0, r1, r2      @ 乘积的低32位是0
+cmp r3, #5
+bne fail
@@ -0,0 +1,28 @@
+@ Thumb下的MOV PC, Rm不切换指令集, 忽略Rm的bit0
+adr r0, mov_pc_thumb + 1
+bx r0
+.syntax unified
+.thumb
+mov_pc_thumb:
+    movs r2, #0
+    adr r0, mov_pc_even     @ bit0为0
+    mov pc, r0
+    b mov_pc_back
+.align 2
+mov_pc_even:
+    adr r0, mov_pc_odd
+    adds r0, #1             @ bit0为1
+    mov pc, r0
+    b mov_pc_back
+.align 2
+mov_pc_odd:
+    movs r2, #1             @ 两次都按Thumb执行到这里
+mov_pc_back:
+    adr r0, mov_pc_arm
+    bx r0
+.arm
+.syntax divided
+.align 2
+mov_pc_arm:
+    cmp r2, #1
+    bne fail
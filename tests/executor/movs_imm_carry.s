@@ -0,0 +1,49 @@
+@ movs的carry来自修饰立即数的展开
+@ 0xf000000f = 0xff循环右移4位, carry为最高位1
+mov r0, #0
+msr apsr_nzcvq, r0
+movs r0, #0xf000000f
+mrs r1, apsr
+and r1, #0xf0000000
+cmp r1, #0xa0000000     @ N,C
+bne fail
+@ 0x3f0 = 0x3f循环右移28位, carry为最高位0
+mov r0, #0x20000000
+msr apsr_nzcvq, r0
+movs r0, #0x3f0
+mrs r1, apsr
+and r1, #0xf0000000
+cmp r1, #0              @ C被清除
+bne fail
+@ 没有循环移位时carry不变
+mov r0, #0x20000000
+msr apsr_nzcvq, r0
+movs r0, #1
+mrs r1, apsr
+and r1, #0xf0000000
+cmp r1, #0x20000000
+bne fail
+@ Thumb
+mov r0, #0
+msr apsr_nzcvq, r0
+adr r0, movs_imm_thumb + 1
+bx r0
+.syntax unified
+.thumb
+movs_imm_thumb:
+    movs.w r2, #0xff000000  @ 0xff循环右移8位, carry为1
+    mov r3, #0
+    adc r3, r3, #0          @ r3 = C
+    movs r4, #1             @ 16位的movs不改变carry
+    mov r5, #0
+    adc r5, r5, #0          @ r5 = C
+    adr r0, movs_imm_arm
+    bx r0
+.arm
+.syntax divided
+.align 2
+movs_imm_arm:
+    cmp r3, #1
+    bne fail
+    cmp r5, #1
+    bne fail
@@ -0,0 +1,44 @@
+@ movt替换高16位, 低16位不变
+movw r0, #0xcafe
+movt r0, #0xbeef
+mov r1, #0xbe000000
+orr r1, #0x00ef0000
+orr r1, #0xca00
+orr r1, #0xfe
+cmp r0, r1              @ 0xbeefcafe
+bne fail
+@ 原来的高16位被覆盖, 不是按位与
+mvn r0, #0              @ 0xffffffff
+movt r0, #0x1234
+mov r1, #0x12000000
+orr r1, #0x00340000
+orr r1, #0xff00
+orr r1, #0xff
+cmp r0, r1              @ 0x1234ffff
+bne fail
+movt r0, #0
+mov r1, #0xff00
+orr r1, #0xff
+cmp r0, r1
+bne fail
+@ Thumb, r5保存结果
+@ imm4不为0, 解码器拼Thumb的imm16时把imm4放错了位置, 执行时要从编码重新拼
+adr r0, movt_thumb + 1
+bx r0
+.syntax unified
+.thumb
+movt_thumb:
+    movw r5, #0xcafe
+    movt r5, #0xbeef
+    adr r0, movt_arm
+    bx r0
+.arm
+.syntax divided
+.align 2
+movt_arm:
+    mov r1, #0xbe000000
+    orr r1, #0x00ef0000
+    orr r1, #0xca00
+    orr r1, #0xfe
+    cmp r5, r1              @ 0xbeefcafe
+    bne fail
@@ -0,0 +1,19 @@
+mrs r0, cpsr
+mov r3, r0              @ 保存原来的CPSR
+orr r0, r0, #0xf0000000 @ N,Z,C,V
+bic r0, r0, #0x1f
+orr r0, r0, #0x1f       @ System模式
+msr cpsr_fsxc, r0
+mrs r1, cpsr
+and r2, r1, #0xf0000000
+cmp r2, #0xf0000000
+bne fail
+and r2, r1, #0x1f
+cmp r2, #0x1f
+bne fail
+msr cpsr_fsxc, r3       @ 回到原来的模式
+mrs r1, cpsr
+and r2, r1, #0x1f
+and r3, r3, #0x1f
+cmp r2, r3
+bne fail
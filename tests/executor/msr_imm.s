@@ -0,0 +1,25 @@
+@ MSR立即数: 只写mask选中的字段
+mov r0, #0x000f0000
+msr apsr_g, r0          @ GE = 0b1111
+msr cpsr_f, #0xf0000000 @ N,Z,C,V
+mrs r1, cpsr
+and r2, r1, #0xf8000000
+cmp r2, #0xf0000000
+bne fail
+and r2, r1, #0x000f0000 @ GE不受影响
+cmp r2, #0x000f0000
+bne fail
+msr cpsr_f, #0x50000000 @ 只有Z,V
+bne fail
+bvc fail
+bmi fail
+bcs fail
+msr cpsr_f, #0x20000000 @ 只有C
+bcc fail
+beq fail
+mrs r1, cpsr
+and r2, r1, #0x000f0000
+cmp r2, #0x000f0000
+bne fail
+mov r0, #0
+msr apsr_g, r0
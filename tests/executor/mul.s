@@ -2,4 +2,24 @@ mov r0, #32
 mov r1, #8
 mul r0, r1
 cmp r0, #256
-bne fail
\ No newline at end of file
+bne fail
+@ 乘积只保留低32位
+mvn r0, #0              @ 0xffffffff
+mvn r1, #0
+mul r2, r0, r1          @ (2^32-1)^2的低32位是1
+cmp r2, #1
+bne fail
+mov r0, #0x80000000
+mov r1, #3
+mul r2, r0, r1
+cmp r2, #0x80000000
+bne fail
+mov r0, #0x10000
+orr r0, #1
+mov r1, #0x10000
+orr r1, #1
+mul r2, r0, r1          @ 0x1_0002_0001
+mov r3, #0x20000
+orr r3, #1
+cmp r2, r3
+bne fail
@@ -0,0 +1,45 @@
+@ muls: N,Z来自32位乘积, C和V保持不变
+@ 乘积为0, C,V原来是1
+mov r0, #0x30000000
+msr apsr_nzcvq, r0
+mov r0, #0
+mov r1, #5
+muls r2, r0, r1
+mrs r3, apsr
+and r3, #0xf0000000
+cmp r3, #0x70000000     @ Z,C,V
+bne fail
+@ 乘积为负数, C,V原来是0
+mov r0, #0
+msr apsr_nzcvq, r0
+mvn r0, #1              @ -2
+mov r1, #3
+muls r2, r0, r1
+mrs r3, apsr
+and r3, #0xf0000000
+cmp r3, #0x80000000     @ N
+bne fail
+cmn r2, #6
+bne fail
+@ 乘积为正数, 原来的N,Z被清除, C保持1
+mov r0, #0xe0000000
+msr apsr_nzcvq, r0
+mov r0, #3
+mov r1, #4
+muls r2, r0, r1
+mrs r3, apsr
+and r3, #0xf0000000
+cmp r3, #0x20000000     @ C
+bne fail
+cmp r2, #12
+bne fail
+@ 乘积超过32位, 只看低32位为0, V保持1
+mov r0, #0x10000000
+msr apsr_nzcvq, r0
+mov r0, #0x10000
+mov r1, #0x10000
+muls r2, r0, r1
+mrs r3, apsr
+and r3, #0xf0000000
+cmp r3, #0x50000000     @ Z,V
+bne fail
@@ -0,0 +1,34 @@
+@ 饱和并行运算: 第一个源操作数是n, 第二个是m, 交换后结果不同
+mov r0, #0x00050000
+orr r0, #9              @ r0 = 0x00050009
+mov r1, #0x00030000
+orr r1, #1              @ r1 = 0x00030001
+qasx r2, r0, r1         @ 高半字5+1, 低半字9-3
+mov r4, #0x00060000
+orr r4, #6
+cmp r2, r4              @ 0x00060006
+bne fail
+qsax r2, r0, r1         @ 高半字5-1, 低半字9+3
+mov r4, #0x00040000
+orr r4, #0xc
+cmp r2, r4              @ 0x0004000c
+bne fail
+qsub8 r2, r0, r1        @ 字节2: 5-3, 字节0: 9-1
+mov r4, #0x00020000
+orr r4, #8
+cmp r2, r4              @ 0x00020008
+bne fail
+@ 16位: -32768-1饱和到-32768
+mov r0, #0x80000000
+orr r0, #0x7f00
+orr r0, #0xff           @ r0 = 0x80007fff
+mov r1, #0x10000        @ r1 = 0x00010000
+qsub16 r2, r0, r1
+cmp r2, r0              @ 0x80007fff
+bne fail
+@ 8位: 127+1饱和到127
+mov r0, #0x7f
+mov r1, #1
+qadd8 r2, r0, r1
+cmp r2, #0x7f
+bne fail
@@ -0,0 +1,27 @@
+@ pop {pc}: 最低位为1时切换到Thumb, 最低两位为0时切换到Arm
+adr r0, pop_pc_thumb + 1
+bx r0
+.syntax unified
+.thumb
+.align 2
+pop_pc_thumb:
+    movs r2, #0             @ r2=2表示两次都到了正确的地址
+    adr r0, pop_pc_thumb2
+    adds r0, #1             @ Thumb地址
+    push {r0}
+    pop {pc}
+    movs r2, #0xf
+.align 2
+pop_pc_thumb2:
+    adds r2, #1
+    adr r0, pop_pc_arm
+    push {r0}
+    pop {pc}
+    movs r2, #0xf
+.arm
+.syntax divided
+.align 2
+pop_pc_arm:
+    add r2, #1
+    cmp r2, #2
+    bne fail
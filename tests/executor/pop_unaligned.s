@@ -0,0 +1,28 @@
+@ 默认不检查SP对齐, 未对齐的SP也能正常push/pop
+@ ARM下的push/pop会被解码成STMDB/LDM, 所以在Thumb下测试
+mov r5, sp
+sub sp, sp, #15         @ sp没有4字节对齐
+adr r0, pop_thumb + 1
+bx r0
+.syntax unified
+.thumb
+pop_thumb:
+    movs r0, #0x11
+    movs r1, #0x22
+    push {r0, r1}
+    movs r0, #0
+    movs r1, #0
+    pop {r0, r1}
+    adr r2, pop_arm
+    bx r2
+.arm
+.syntax divided
+.align 2
+pop_arm:
+    cmp r0, #0x11
+    bne fail
+    cmp r1, #0x22
+    bne fail
+    add sp, sp, #15
+    cmp sp, r5
+    bne fail
@@ -0,0 +1,33 @@
+@ 前变址, 减去移位后的寄存器偏移, 写回基址
+mov r6, sp
+sub sp, #32
+mov r0, #0x55
+str r0, [sp, #4]
+add r1, sp, #16         @ r1 = SP+16
+mov r2, #3
+ldr r3, [r1, -r2, lsl #2]!
+cmp r3, #0x55
+bne fail
+add r4, sp, #4
+cmp r1, r4              @ r1 = SP+16-3*4, 只减了一次
+bne fail
+@ 不写回时基址不变
+add r1, sp, #16
+ldr r3, [r1, -r2, lsl #2]
+cmp r3, #0x55
+bne fail
+add r4, sp, #16
+cmp r1, r4
+bne fail
+@ STR也一样
+mov r0, #0x66
+add r1, sp, #28
+mov r2, #1
+str r0, [r1, -r2, lsl #3]!
+add r4, sp, #20
+cmp r1, r4
+bne fail
+ldr r3, [sp, #20]
+cmp r3, #0x66
+bne fail
+mov sp, r6
@@ -0,0 +1,108 @@
+@ push {r0-r3}和stmdb sp!, {r0-r3}写出的内存和SP一样
+mov r0, #0x11
+mov r1, #0x22
+mov r2, #0x33
+mov r3, #0x44
+mov r5, sp
+push {r0-r3}
+mov r6, sp
+ldm r6, {r7-r10}
+mov sp, r5
+mov r0, #0
+mov r1, #0
+mov r2, #0
+mov r3, #0
+str r0, [r6]
+str r0, [r6, #4]
+str r0, [r6, #8]
+str r0, [r6, #12]
+mov r0, #0x11
+mov r1, #0x22
+mov r2, #0x33
+mov r3, #0x44
+stmdb sp!, {r0-r3}
+cmp sp, r6
+bne fail
+ldr r4, [sp]
+cmp r4, r7
+bne fail
+ldr r4, [sp, #4]
+cmp r4, r8
+bne fail
+ldr r4, [sp, #8]
+cmp r4, r9
+bne fail
+ldr r4, [sp, #12]
+cmp r4, r10
+bne fail
+@ pop {r0-r3}和ldmia sp!, {r0-r3}读出的寄存器和SP一样
+pop {r0-r3}
+mov r6, sp
+mov r7, r0
+mov r8, r1
+mov r9, r2
+mov r10, r3
+sub sp, sp, #16
+mov r0, #0
+mov r1, #0
+mov r2, #0
+mov r3, #0
+ldmia sp!, {r0-r3}
+cmp sp, r6
+bne fail
+cmp sp, r5
+bne fail
+cmp r0, r7
+bne fail
+cmp r1, r8
+bne fail
+cmp r2, r9
+bne fail
+cmp r3, r10
+bne fail
+cmp r0, #0x11
+bne fail
+cmp r3, #0x44
+bne fail
+@ ldm的四种模式都写回基址±4n
+sub r0, sp, #64
+mov r3, r0
+mov r1, #0x55
+mov r2, #0x66
+stmia r3, {r1, r2}
+ldmia r0!, {r1, r2}
+sub r4, r0, r3
+cmp r4, #8
+bne fail
+mov r0, r3
+sub r3, r3, #4
+ldmib r3!, {r1, r2}
+cmp r1, #0x55
+bne fail
+cmp r2, #0x66
+bne fail
+sub r4, r3, r0
+cmp r4, #4
+bne fail
+add r3, r0, #8
+ldmdb r3!, {r1, r2}
+cmp r3, r0
+bne fail
+cmp r1, #0x55
+bne fail
+cmp r2, #0x66
+bne fail
+add r3, r0, #4
+ldmda r3!, {r1, r2}
+cmp r1, #0x55
+bne fail
+cmp r2, #0x66
+bne fail
+sub r4, r0, r3
+cmp r4, #4
+bne fail
+@ Rn在列表中时不写回, 保留读到的值
+mov r3, r0
+.inst 0xe8b3000c       @ ldmia r3!, {r2, r3}, 汇编器不接受这种写法
+cmp r3, #0x66
+bne fail
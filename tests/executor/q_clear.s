@@ -0,0 +1,28 @@
+@ Q是粘滞的, 只能通过MSR APSR_nzcvq清除
+mov r0, #0
+msr APSR_nzcvq, r0
+mov r1, #1000
+ssat r2, #8, r1         @ 饱和到127, Q置位
+cmp r2, #127
+bne fail
+mrs r0, APSR
+tst r0, #0x08000000
+beq fail
+ssat r2, #8, r2         @ 不饱和, Q保持
+mrs r0, APSR
+tst r0, #0x08000000
+beq fail
+msr APSR_g, r0          @ 只写GE, Q保持
+mrs r0, APSR
+tst r0, #0x08000000
+beq fail
+bic r0, r0, #0x08000000
+msr APSR_nzcvq, r0      @ 写0清除Q
+mrs r0, APSR
+tst r0, #0x08000000
+bne fail
+ssat r2, #8, r1
+msr APSR_nzcvq, #0      @ 立即数形式也能清除
+mrs r0, APSR
+tst r0, #0x08000000
+bne fail
@@ -0,0 +1,57 @@
+@ qadd16: 低半字20000+20000=40000饱和到32767, 高半字1+2=3不受影响
+mov r0, #0x00010000
+orr r0, #0x4e00
+orr r0, #0x20           @ r0 = 0x00014e20
+mov r1, #0x00020000
+orr r1, #0x4e00
+orr r1, #0x20           @ r1 = 0x00024e20
+qadd16 r2, r0, r1
+mov r4, #0x00030000
+orr r4, #0x7f00
+orr r4, #0xff
+cmp r2, r4              @ 0x00037fff
+bne fail
+@ qadd16: 高半字饱和到-32768, 低半字1+1=2
+mov r0, #0x80000000
+orr r0, #1              @ r0 = 0x80000001
+mov r1, #0xff000000
+orr r1, #0x00ff0000
+orr r1, #1              @ r1 = 0xffff0001
+qadd16 r2, r0, r1
+mov r4, #0x80000000
+orr r4, #2
+cmp r2, r4              @ 0x80000002
+bne fail
+@ uqadd16: 低半字0xffff+1饱和到0xffff, 高半字1+1=2
+mov r0, #0x00010000
+orr r0, #0xff00
+orr r0, #0xff           @ r0 = 0x0001ffff
+mov r1, #0x00010000
+orr r1, #1              @ r1 = 0x00010001
+uqadd16 r2, r0, r1
+mov r4, #0x00020000
+orr r4, #0xff00
+orr r4, #0xff
+cmp r2, r4              @ 0x0002ffff
+bne fail
+@ qasx/qsax: 第一个源操作数是n, 第二个是m, 交换后结果不同
+mov r0, #0x00050000
+orr r0, #9              @ r0 = 0x00050009
+mov r1, #0x00030000
+orr r1, #1              @ r1 = 0x00030001
+qasx r2, r0, r1         @ 低半字9-3, 高半字5+1
+mov r4, #0x00060000
+orr r4, #6
+cmp r2, r4              @ 0x00060006
+bne fail
+uqasx r2, r0, r1
+cmp r2, r4
+bne fail
+qsax r2, r0, r1         @ 低半字9+3, 高半字5-1
+mov r4, #0x00040000
+orr r4, #0xc
+cmp r2, r4              @ 0x0004000c
+bne fail
+uqsax r2, r0, r1
+cmp r2, r4
+bne fail
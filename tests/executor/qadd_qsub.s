@@ -0,0 +1,84 @@
+@ qadd/qsub/qdadd/qdsub: 32位有符号饱和, 饱和时Q置位
+@ 语法是Rd, Rm, Rn, qdadd/qdsub把Rn加倍
+mov r0, #0
+msr APSR_nzcvq, r0
+@ 两个大的正数相加饱和到0x7fffffff
+mov r0, #0x60000000
+mov r1, #0x70000000
+qadd r2, r0, r1
+mvn r3, #0x80000000
+cmp r2, r3
+bne fail
+mrs r4, APSR
+tst r4, #0x08000000
+beq fail
+@ 不饱和时结果正常, Q不变
+mov r0, #0
+msr APSR_nzcvq, r0
+mov r0, #100
+mvn r1, #49             @ -50
+qadd r2, r0, r1
+cmp r2, #50
+bne fail
+qsub r2, r0, r1         @ 100 - (-50)
+cmp r2, #150
+bne fail
+qsub r2, r1, r0         @ -50 - 100
+mvn r3, #149
+cmp r2, r3
+bne fail
+mrs r4, APSR
+tst r4, #0x08000000
+bne fail
+@ qsub向下饱和到0x80000000
+mov r0, #0x80000000
+mov r1, #1
+qsub r2, r0, r1
+cmp r2, #0x80000000
+bne fail
+mrs r4, APSR
+tst r4, #0x08000000
+beq fail
+@ qdadd不饱和时: 10 + 2*7
+mov r0, #0
+msr APSR_nzcvq, r0
+mov r0, #10
+mov r1, #7
+qdadd r2, r0, r1
+cmp r2, #24
+bne fail
+qdsub r2, r0, r1        @ 10 - 2*7
+mvn r3, #3
+cmp r2, r3
+bne fail
+mvn r1, #6              @ -7
+qdadd r2, r0, r1        @ 10 + 2*(-7)
+cmp r2, r3
+bne fail
+mrs r4, APSR
+tst r4, #0x08000000
+bne fail
+@ qdadd: 加倍时饱和, 再加上负数后在范围内, Q仍然置位
+mvn r0, #0x30000000     @ 0xcfffffff
+mov r1, #0x40000000
+qdadd r2, r0, r1        @ sat(0x80000000) = 0x7fffffff, 再加0xcfffffff
+mov r3, #0x4f000000
+orr r3, #0x00ff0000
+orr r3, #0xff00
+orr r3, #0xfe           @ 0x4ffffffe
+cmp r2, r3
+bne fail
+mrs r4, APSR
+tst r4, #0x08000000
+beq fail
+@ qdsub: 0 - sat(2*0x80000000) = 0 - 0x80000000, 再饱和到0x7fffffff
+mov r0, #0
+msr APSR_nzcvq, r0
+mov r1, #0x80000000
+qdsub r2, r0, r1
+mvn r3, #0x80000000
+cmp r2, r3
+bne fail
+mrs r4, APSR
+tst r4, #0x08000000
+beq fail
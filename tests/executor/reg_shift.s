@@ -0,0 +1,20 @@
+mov r1, #1
+mov r2, #0x104          @ 移位量只取低8位, 即4
+mov r0, r1, lsl r2
+cmp r0, #16
+bne fail
+mov r2, #0x120          @ 低8位为32
+mov r0, r1, lsl r2
+cmp r0, #0
+bne fail
+mov r1, #0x80000000
+mov r2, #0x121          @ 低8位为33
+mov r0, r1, asr r2
+cmp r0, #0xffffffff
+bne fail
+@ 立即数移位量: 第二个操作数是r1左移2位
+mov r1, #3
+mov r3, #1
+add r0, r3, r1, lsl #2
+cmp r0, #13
+bne fail
@@ -0,0 +1,12 @@
+@ 不是严格模式时, 应为1的位是0的CLZ照常执行
+mov r1, #0x00010000
+mov r0, #0
+.word 0xe1600011        @ clz r0, r1, 第19-16位和第11-8位是0
+cmp r0, #15
+bne fail
+@ 应为0的位是1的MUL
+mov r1, #3
+mov r2, #5
+.word 0xe000f291        @ mul r0, r1, r2, 第15-12位是1
+cmp r0, #15
+bne fail
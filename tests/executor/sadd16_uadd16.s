@@ -0,0 +1,53 @@
+@ sadd16: 低半字1+1=2, 高半字-1+0=-1
+mov r0, #0xff000000
+orr r0, #0x00ff0000
+orr r0, #1              @ r0 = 0xffff0001
+mov r1, #1
+sadd16 r2, r0, r1
+mrs r3, apsr
+and r3, #0x000f0000
+cmp r3, #0x00030000     @ GE = 0b0011
+bne fail
+mov r4, #0xff000000
+orr r4, #0x00ff0000
+orr r4, #2
+cmp r2, r4              @ 0xffff0002
+bne fail
+@ sadd16: 低半字-1+0=-1, 高半字1+1=2
+mov r0, #0x00010000
+orr r0, #0xff00
+orr r0, #0xff           @ r0 = 0x0001ffff
+mov r1, #0x00010000
+sadd16 r2, r0, r1
+mrs r3, apsr
+and r3, #0x000f0000
+cmp r3, #0x000c0000     @ GE = 0b1100
+bne fail
+mov r4, #0x00020000
+orr r4, #0xff00
+orr r4, #0xff
+cmp r2, r4              @ 0x0002ffff
+bne fail
+@ uadd16: 低半字0xffff+1进位, 高半字不进位
+mov r0, #0xff00
+orr r0, #0xff           @ r0 = 0x0000ffff
+mov r1, #1
+uadd16 r2, r0, r1
+mrs r3, apsr
+and r3, #0x000f0000
+cmp r3, #0x00030000     @ GE = 0b0011
+bne fail
+cmp r2, #0
+bne fail
+@ uadd16: 高半字0xffff+1进位, 低半字不进位
+mov r0, #0xff000000
+orr r0, #0x00ff0000     @ r0 = 0xffff0000
+mov r1, #0x00010000
+orr r1, #5
+uadd16 r2, r0, r1
+mrs r3, apsr
+and r3, #0x000f0000
+cmp r3, #0x000c0000     @ GE = 0b1100
+bne fail
+cmp r2, #5
+bne fail
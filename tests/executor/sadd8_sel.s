@@ -0,0 +1,37 @@
+@ sadd8之后紧接着sel, sel要用sadd8刚写入的GE
+@ 字节0: 1+1=2, 字节1: 0x80+0=-128, 字节2: 0x10+0x10=32, 字节3: 0xff+0xff=-2
+@ GE = 0b0101
+mov r0, #0xff000000
+orr r0, #0x00100000
+orr r0, #0x8000
+orr r0, #1              @ r0 = 0xff108001
+mov r1, #0xff000000
+orr r1, #0x00100000
+orr r1, #1              @ r1 = 0xff100001
+sadd8 r2, r0, r1
+mov r3, #0x11000000
+orr r3, #0x00110000
+orr r3, #0x1100
+orr r3, #0x11           @ r3 = 0x11111111
+mov r4, #0x22000000
+orr r4, #0x00220000
+orr r4, #0x2200
+orr r4, #0x22           @ r4 = 0x22222222
+sel r5, r3, r4
+mov r6, #0x22000000
+orr r6, #0x00110000
+orr r6, #0x2200
+orr r6, #0x11
+cmp r5, r6              @ 0x22112211
+bne fail
+@ 再来一次sadd8, GE变为0b1010, sel的结果要跟着变
+mvn r0, r0              @ r0 = 0x00ef7ffe
+mov r1, #0
+sadd8 r2, r0, r1
+sel r5, r3, r4
+mov r6, #0x11000000
+orr r6, #0x00220000
+orr r6, #0x1100
+orr r6, #0x22
+cmp r5, r6              @ 0x11221122
+bne fail
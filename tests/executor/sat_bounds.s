@@ -0,0 +1,99 @@
+@ n=8和n=16的边界: 正好在范围内的不饱和, 超出1的饱和
+@ r4记录Q, 每次检查前先清除
+mov r1, #127
+mov r0, #0
+msr APSR_nzcvq, r0
+ssat r2, #8, r1
+mrs r4, APSR
+cmp r2, #127
+bne fail
+tst r4, #0x08000000
+bne fail
+mvn r1, #127            @ -128
+ssat r2, #8, r1
+mrs r4, APSR
+cmp r2, r1
+bne fail
+tst r4, #0x08000000
+bne fail
+mov r1, #0x7f00
+orr r1, #0xff           @ 32767
+ssat r2, #16, r1
+mrs r4, APSR
+cmp r2, r1
+bne fail
+tst r4, #0x08000000
+bne fail
+add r1, r1, #1          @ 32768
+ssat r2, #16, r1
+mrs r4, APSR
+sub r1, r1, #1
+cmp r2, r1
+bne fail
+tst r4, #0x08000000
+beq fail
+mov r0, #0
+msr APSR_nzcvq, r0
+mvn r1, #0x7f00
+bic r1, #0xff           @ -32768
+ssat r2, #16, r1
+mrs r4, APSR
+cmp r2, r1
+bne fail
+tst r4, #0x08000000
+bne fail
+sub r1, r1, #1          @ -32769
+ssat r2, #16, r1
+mrs r4, APSR
+add r1, r1, #1
+cmp r2, r1
+bne fail
+tst r4, #0x08000000
+beq fail
+@ 无符号: 0和65535不饱和, 65536饱和
+mov r0, #0
+msr APSR_nzcvq, r0
+mov r1, #0
+usat r2, #16, r1
+cmp r2, #0
+bne fail
+mov r1, #0xff00
+orr r1, #0xff
+usat r2, #16, r1
+mrs r4, APSR
+cmp r2, r1
+bne fail
+tst r4, #0x08000000
+bne fail
+add r3, r1, #1
+usat r2, #16, r3
+mrs r4, APSR
+cmp r2, r1
+bne fail
+tst r4, #0x08000000
+beq fail
+mov r0, #0
+msr APSR_nzcvq, r0
+mov r1, #256
+sub r1, r1, #1
+usat r2, #8, r1
+mrs r4, APSR
+cmp r2, #255
+bne fail
+tst r4, #0x08000000
+bne fail
+@ n=32时不会溢出
+mov r1, #0x80000000
+mvn r1, r1              @ 0x7fffffff
+ssat r2, #32, r1
+mrs r4, APSR
+cmp r2, r1
+bne fail
+tst r4, #0x08000000
+bne fail
+usat r2, #31, r1
+mrs r4, APSR
+cmp r2, r1
+bne fail
+tst r4, #0x08000000
+bne fail
@@ -0,0 +1,40 @@
+@ 64位减法, 低位借位: 0x00000001_00000000 - 0x00000000_00000001
+mov r0, #0
+mov r1, #1
+mov r2, #1
+mov r3, #0
+subs r4, r0, r2
+sbc r5, r1, r3
+cmp r4, #0xffffffff
+bne fail
+cmp r5, #0
+bne fail
+@ 64位减法, 不借位: 0x00000002_00000005 - 0x00000001_00000003
+mov r0, #5
+mov r1, #2
+mov r2, #3
+mov r3, #1
+subs r4, r0, r2
+sbc r5, r1, r3
+cmp r4, #2
+bne fail
+cmp r5, #1
+bne fail
+@ 64位取负, 低位借位: 0 - 0x00000000_00000001
+mov r0, #1
+mov r1, #0
+rsbs r4, r0, #0
+rsc r5, r1, #0
+cmp r4, #0xffffffff
+bne fail
+cmp r5, #0xffffffff
+bne fail
+@ 64位取负, 不借位: 0 - 0x00000001_00000000
+mov r0, #0
+mov r1, #1
+rsbs r4, r0, #0
+rsc r5, r1, #0
+cmp r4, #0
+bne fail
+cmp r5, #0xffffffff
+bne fail
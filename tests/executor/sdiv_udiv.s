@@ -0,0 +1,59 @@
+@ -7 / 2 向0取整得-3
+mvn r0, #6              @ -7
+mov r1, #2
+sdiv r2, r0, r1
+mvn r3, #2              @ -3
+cmp r2, r3
+bne fail
+@ udiv把-7当成无符号数
+udiv r2, r0, r1
+mvn r3, #0x80000003     @ 0x7ffffffc
+cmp r2, r3
+bne fail
+@ INT_MIN / -1溢出, 结果是INT_MIN
+mov r0, #0x80000000
+mvn r1, #0
+sdiv r2, r0, r1
+cmp r2, r0
+bne fail
+@ 除以0结果是0
+mov r0, #100
+mov r1, #0
+mov r2, #1
+sdiv r2, r0, r1
+cmp r2, #0
+bne fail
+mov r2, #1
+udiv r2, r0, r1
+cmp r2, #0
+bne fail
+@ udiv: 0xffffffff / 0x10
+mvn r0, #0
+mov r1, #0x10
+udiv r2, r0, r1
+mvn r3, #0xf0000000     @ 0x0fffffff
+cmp r2, r3
+bne fail
+@ Thumb下是32位编码, r5记录结果
+adr r0, div_thumb + 1
+bx r0
+.syntax unified
+.thumb
+div_thumb:
+    movs r0, #7
+    negs r0, r0
+    movs r1, #2
+    sdiv r5, r0, r1
+    movs r1, #0
+    udiv r6, r0, r1
+    adr r0, div_arm
+    bx r0
+.arm
+.syntax divided
+.align 2
+div_arm:
+    mvn r3, #2
+    cmp r5, r3
+    bne fail
+    cmp r6, #0
+    bne fail
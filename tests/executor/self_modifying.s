@@ -0,0 +1,16 @@
+@ 执行过的指令被改写后, 再次执行的是新的指令
+mov r2, #0
+smc_insn:
+mov r0, #1
+add r2, #1
+cmp r2, #2
+beq smc_done
+mov r1, #0xe3000000
+orr r1, #0x00a00000
+orr r1, #2              @ mov r0, #2
+adr r3, smc_insn
+str r1, [r3]
+b smc_insn
+smc_done:
+cmp r0, #2
+bne fail
@@ -0,0 +1,35 @@
+@ setend be之后按大端读写字和半字
+mov r0, #0x11000000
+orr r0, #0x00220000
+orr r0, #0x3300
+orr r0, #0x44           @ 0x11223344
+str r0, [sp, #-4]!
+adr r0, setend_thumb + 1
+bx r0
+.syntax unified
+.thumb
+setend_thumb:
+    setend be
+    ldr r1, [sp]
+    ldrh r2, [sp]
+    ldrb r3, [sp]
+    setend le
+    adr r0, setend_arm
+    bx r0
+.arm
+.syntax divided
+.align 2
+setend_arm:
+    add sp, #4
+    mov r4, #0x44000000
+    orr r4, #0x00330000
+    orr r4, #0x2200
+    orr r4, #0x11
+    cmp r1, r4              @ 0x44332211
+    bne fail
+    mov r4, #0x4400
+    orr r4, #0x33
+    cmp r2, r4              @ 0x4433
+    bne fail
+    cmp r3, #0x44           @ 字节不受影响
+    bne fail
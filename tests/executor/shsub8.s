@@ -0,0 +1,39 @@
+@ shsub8: 每个字节按有符号数相减后算术右移一位
+mov r0, #0x7f000000
+orr r0, #0x00800000
+orr r0, #0x1000         @ r0 = 0x7f801000 (127, -128, 16, 0)
+mov r1, #0x81000000
+orr r1, #0x00010000
+orr r1, #0x2000
+orr r1, #1              @ r1 = 0x81012001 (-127, 1, 32, 1)
+shsub8 r2, r0, r1
+mov r4, #0x7f000000
+orr r4, #0x00bf0000
+orr r4, #0xf800
+orr r4, #0xff
+cmp r2, r4              @ 0x7fbff8ff (127, -65, -8, -1)
+bne fail
+@ uhsub8: 差为负数时结果的高位也是1
+mov r0, #0x10
+mov r1, #0x20
+uhsub8 r2, r0, r1
+cmp r2, #0xf8
+bne fail
+@ 同样的操作数, 有符号和无符号的结果不同: 0x80 - 0x01
+mov r0, #0x80
+mov r1, #0x01
+shsub8 r2, r0, r1       @ (-128 - 1) >> 1 = -65
+cmp r2, #0xbf
+bne fail
+uhsub8 r3, r0, r1       @ (128 - 1) >> 1 = 63
+cmp r3, #0x3f
+bne fail
+@ 0x01 - 0xff: 有符号是1 - (-1) = 2, 无符号是1 - 255 = -254
+mov r0, #0x01
+mov r1, #0xff
+shsub8 r2, r0, r1
+cmp r2, #0x01
+bne fail
+uhsub8 r3, r0, r1
+cmp r3, #0x81
+bne fail
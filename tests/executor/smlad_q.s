@@ -0,0 +1,67 @@
+@ smuad: 两个乘积都是0x40000000, 和0x80000000超出了有符号32位, 设置Q
+msr apsr_nzcvq, #0
+mov r2, #0x80000000
+orr r2, #0x8000         @ -32768, -32768
+smuad r0, r2, r2
+cmp r0, #0x80000000
+bne fail
+mrs r4, apsr
+tst r4, #0x08000000
+beq fail
+@ smlad: 0x10000000+0x10000000+0x60000000 = 0x80000000, 设置Q
+msr apsr_nzcvq, #0
+mov r2, #0x40000000
+orr r2, #0x4000
+mov r3, #0x60000000
+smlad r0, r2, r2, r3
+cmp r0, #0x80000000
+bne fail
+mrs r4, apsr
+tst r4, #0x08000000
+beq fail
+@ smlsd: 0x7fff*0x7fff-(-1*0x7fff)+0x40008000 = 0x80000000, 设置Q
+msr apsr_nzcvq, #0
+mvn r2, #0x8000         @ 0xffff7fff
+mvn r3, #0x80000000
+bic r3, #0x8000         @ 0x7fff7fff
+mov r1, #0x40000000
+orr r1, #0x8000
+smlsd r0, r2, r3, r1
+cmp r0, #0x80000000
+bne fail
+mrs r4, apsr
+tst r4, #0x08000000
+beq fail
+@ smlad: 半字是有符号的, -1*3+(-1)*2+0 = -5, 不设置Q
+msr apsr_nzcvq, #0
+mvn r2, #0
+mov r3, #3
+orr r3, #0x20000
+mov r1, #0
+smlad r0, r2, r3, r1
+cmn r0, #5
+bne fail
+mrs r4, apsr
+tst r4, #0x08000000
+bne fail
+@ smusd: -1*4-2*3 = -10
+mov r2, #0x20000
+orr r2, #0xff
+orr r2, #0xff00         @ 0x0002ffff
+mov r3, #4
+orr r3, #0x30000
+smusd r0, r2, r3
+cmn r0, #10
+bne fail
+@ smlald: -1*3+(-1)*2+0x1_00000000 = 0xfffffffb
+msr apsr_nzcvq, #0
+mvn r2, #0
+mov r3, #3
+orr r3, #0x20000
+mov r0, #0
+mov r1, #1
+smlald r0, r1, r2, r3
+cmn r0, #5
+bne fail
+cmp r1, #0
+bne fail
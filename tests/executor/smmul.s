@@ -0,0 +1,50 @@
+@ SMMUL/SMMLA/SMMLS: 有符号64位乘积的高32位, R后缀加0x80000000舍入
+mvn r0, #1              @ -2
+mov r1, #3
+smmul r2, r0, r1        @ -6的高32位
+mvn r3, #0
+cmp r2, r3
+bne fail
+mvn r0, #0              @ -1
+mvn r1, #0
+smmul r2, r0, r1        @ 1的高32位
+cmp r2, #0
+bne fail
+mov r0, #0x80000000
+mov r1, #0x80000000
+smmul r2, r0, r1        @ 2^62
+cmp r2, #0x40000000
+bne fail
+mov r0, #0x40000000
+mov r1, #2
+smmulr r2, r0, r1       @ 0x80000000, 舍入后进位
+cmp r2, #1
+bne fail
+@ smmla: (Ra<<32) + Rn*Rm
+mvn r0, #1              @ -2
+mov r1, #3
+mov r3, #5
+smmla r2, r0, r1, r3    @ 5*2^32 - 6
+cmp r2, #4
+bne fail
+@ 累加值是负数, 结果回绕
+mov r3, #0x80000000
+mvn r0, #0
+mov r1, #1
+smmla r2, r0, r1, r3    @ -2^63 - 1的[63:32]位
+mvn r4, #0x80000000
+cmp r2, r4
+bne fail
+@ smmls: (Ra<<32) - Rn*Rm
+mvn r0, #1              @ -2
+mov r1, #3
+mov r3, #5
+smmls r2, r0, r1, r3    @ 5*2^32 + 6
+cmp r2, #5
+bne fail
+mov r0, #1
+mov r1, #1
+mov r3, #0
+smmlsr r2, r0, r1, r3   @ -1 + 0x80000000
+cmp r2, #0
+bne fail
@@ -0,0 +1,54 @@
+@ 软件中断: 写入触发寄存器后, 在下一条指令之前进入处理程序
+adr r0, software_interrupt_handler
+mov r1, #0x40000000
+str r0, [r1, #12]       @ 处理程序的地址
+mrs r9, cpsr            @ 保存原来的CPSR
+bic r2, r9, #0x80
+msr cpsr_c, r2          @ 清除CPSR.I
+mov r5, #0
+mov r6, #0xff
+mov r7, #0
+mov r0, #1
+str r0, [r1, #8]        @ 触发, str先执行完
+mov r5, #1
+cmp r7, #1              @ 处理程序运行了一次
+bne fail
+cmp r6, #0              @ 运行时mov r5, #1还没有执行
+bne fail
+cmp r5, #1              @ 返回后继续执行
+bne fail
+ldr r0, [r1, #8]
+cmp r0, #0              @ 进入时已经不再挂起
+bne fail
+mrs r2, cpsr
+bic r3, r9, #0x80
+cmp r2, r3              @ 恢复了CPSR
+bne fail
+@ CPSR.I为1时不进入, 清除后才进入
+msr cpsr_c, r9
+mov r0, #1
+str r0, [r1, #8]
+mov r5, #2
+cmp r7, #1
+bne fail
+ldr r0, [r1, #8]
+cmp r0, #1              @ 仍然挂起
+bne fail
+bic r2, r9, #0x80
+msr cpsr_c, r2
+mov r5, #3
+cmp r7, #2
+bne fail
+cmp r6, #2              @ 在msr之后, mov r5, #3之前运行
+bne fail
+msr cpsr_c, r9
+b software_interrupt_end
+software_interrupt_handler:
+    mrs r4, cpsr
+    and r4, #0x9f
+    cmp r4, #0x92       @ IRQ模式, CPSR.I为1
+    bne fail
+    mov r6, r5
+    add r7, #1
+    subs pc, lr, #4
+software_interrupt_end:
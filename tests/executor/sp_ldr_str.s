@@ -0,0 +1,84 @@
+@ 以SP为基址的LDR/STR, 函数的局部变量都这样访问
+mov r6, sp              @ 保存SP, 最后检查是否恢复
+sub sp, #16
+mov r0, #0x11
+mov r1, #0x22
+str r0, [sp, #4]        @ 偏移: SP不变
+str r1, [sp, #12]
+cmp sp, r6
+beq fail
+add r2, sp, #16
+cmp r2, r6
+bne fail
+ldr r2, [sp, #4]
+cmp r2, #0x11
+bne fail
+ldr r2, [r6, #-4]       @ 同一个位置: SP+12
+cmp r2, #0x22
+bne fail
+@ 前变址写回, 相当于push
+mov r0, #0x33
+str r0, [sp, #-4]!
+add r2, sp, #20
+cmp r2, r6
+bne fail
+ldr r2, [sp]
+cmp r2, #0x33
+bne fail
+@ 后变址写回, 相当于pop
+ldr r2, [sp], #8        @ 读SP处, 然后SP+8
+cmp r2, #0x33
+bne fail
+add r2, sp, #12
+cmp r2, r6
+bne fail
+ldr r2, [sp], #8        @ 读旧SP+8处, 即str r0, [sp, #4]写的位置
+cmp r2, #0x11
+bne fail
+add r2, sp, #4
+cmp r2, r6
+bne fail
+@ 后变址减
+str r1, [sp], #-4
+ldr r2, [sp, #4]
+cmp r2, #0x22
+bne fail
+@ Thumb: 16位的LDR/STR Rt, [SP, #imm]和32位的写回形式
+adr r0, sp_ldr_str_thumb + 1
+bx r0
+.syntax unified
+.thumb
+.align 2
+sp_ldr_str_thumb:
+    mov sp, r6
+    sub sp, #16
+    movs r0, #0x44
+    str r0, [sp, #8]
+    movs r0, #0
+    ldr r0, [sp, #8]
+    adds r0, #0             @ 16位的LDR不改变SP
+    mov r2, sp
+    adds r2, #16
+    str.w r0, [sp, #-4]!
+    mov r3, sp
+    adds r3, #20
+    ldr.w r1, [sp], #4
+    mov r4, sp
+    adds r4, #16
+    mov sp, r6
+    adr r5, sp_ldr_str_arm
+    bx r5
+.arm
+.syntax divided
+.align 2
+sp_ldr_str_arm:
+    cmp r0, #0x44
+    bne fail
+    cmp r1, #0x44
+    bne fail
+    cmp r2, r6
+    bne fail
+    cmp r3, r6
+    bne fail
+    cmp r4, r6
+    bne fail
@@ -0,0 +1,80 @@
+mov r0, #0
+msr APSR_nzcvq, r0
+@ 范围内的值不变, Q不变
+mov r1, #100
+ssat r2, #8, r1
+cmp r2, #100
+bne fail
+mvn r1, #127            @ -128
+ssat r2, #8, r1
+cmp r2, r1
+bne fail
+mov r1, #255
+usat r2, #8, r1
+cmp r2, #255
+bne fail
+mrs r0, APSR
+tst r0, #0x08000000
+bne fail
+@ ssat: 超过最大值饱和到127, Q置位
+mov r1, #128
+ssat r2, #8, r1
+cmp r2, #127
+bne fail
+mrs r0, APSR
+tst r0, #0x08000000
+beq fail
+@ ssat: 小于最小值饱和到-128
+mov r0, #0
+msr APSR_nzcvq, r0
+mvn r1, #128            @ -129
+ssat r2, #8, r1
+mvn r3, #127
+cmp r2, r3
+bne fail
+mrs r0, APSR
+tst r0, #0x08000000
+beq fail
+@ usat: 负数饱和到0, 超过最大值饱和到255
+mov r0, #0
+msr APSR_nzcvq, r0
+mvn r1, #0
+usat r2, #8, r1
+cmp r2, #0
+bne fail
+mrs r0, APSR
+tst r0, #0x08000000
+beq fail
+mov r0, #0
+msr APSR_nzcvq, r0
+mov r1, #256
+usat r2, #8, r1
+cmp r2, #255
+bne fail
+mrs r0, APSR
+tst r0, #0x08000000
+beq fail
+@ 先移位再饱和
+mov r0, #0
+msr APSR_nzcvq, r0
+mov r1, #0x40
+ssat r2, #8, r1, lsl #1 @ 128
+cmp r2, #127
+bne fail
+mov r1, #0x400
+usat r2, #8, r1, asr #3 @ 128
+cmp r2, #128
+bne fail
+mvn r1, #0xff           @ 0xffffff00
+ssat r2, #16, r1, asr #4 @ -16
+mvn r3, #15
+cmp r2, r3
+bne fail
+@ 位宽为32和0的边界
+mov r1, #0x80000000
+ssat r2, #32, r1
+cmp r2, r1
+bne fail
+usat r2, #0, r1
+cmp r2, #0
+bne fail
@@ -0,0 +1,76 @@
+@ stmia: Rn是编号最小的寄存器, 存的是原来的基址
+sub r0, sp, #64
+mov r3, r0
+mov r1, #0x11
+stmia r0!, {r0, r1}
+ldr r2, [r3]
+cmp r2, r3
+bne fail
+ldr r2, [r3, #4]
+cmp r2, #0x11
+bne fail
+sub r2, r0, r3
+cmp r2, #8              @ 写回了基址+8
+bne fail
+@ stmia: Rn不是编号最小的寄存器, 存的是写回后的基址
+sub r2, sp, #64
+mov r3, r2
+mov r1, #0x22
+stmia r2!, {r1, r2}
+ldr r4, [r3]
+cmp r4, #0x22
+bne fail
+ldr r4, [r3, #4]
+cmp r4, r2
+bne fail
+sub r4, r2, r3
+cmp r4, #8
+bne fail
+@ stmia: 不写回时存的是原来的基址
+sub r2, sp, #64
+mov r1, #0x33
+stmia r2, {r1, r2}
+ldr r4, [r2, #4]
+cmp r4, r2
+bne fail
+@ stmdb: 编号小的寄存器在低地址, 写回基址-8
+sub r0, sp, #64
+mov r3, r0
+mov r1, #0x44
+mov r2, #0x55
+stmdb r0!, {r1, r2}
+sub r4, r3, r0
+cmp r4, #8
+bne fail
+ldr r4, [r3, #-8]
+cmp r4, #0x44
+bne fail
+ldr r4, [r3, #-4]
+cmp r4, #0x55
+bne fail
+@ stmib: 从基址+4开始
+sub r0, sp, #64
+mov r3, r0
+stmib r0!, {r1, r2}
+sub r4, r0, r3
+cmp r4, #8
+bne fail
+ldr r4, [r3, #4]
+cmp r4, #0x44
+bne fail
+ldr r4, [r3, #8]
+cmp r4, #0x55
+bne fail
+@ stmda: 最后一个寄存器存在基址
+sub r0, sp, #64
+mov r3, r0
+stmda r0!, {r1, r2}
+sub r4, r3, r0
+cmp r4, #8
+bne fail
+ldr r4, [r3, #-4]
+cmp r4, #0x44
+bne fail
+ldr r4, [r3]
+cmp r4, #0x55
+bne fail
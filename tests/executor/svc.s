@@ -0,0 +1,21 @@
+@ 没有设置svc_handler时SVC什么也不做, 继续执行下一条指令
+@ 解码器目前只能解出Thumb的SVC, r5=1表示执行到了SVC之后
+mov r5, #0
+adr r0, svc_thumb + 1
+bx r0
+.syntax unified
+.thumb
+svc_thumb:
+    movs r1, #7
+    svc #0x42
+    movs r5, #1
+    adr r0, svc_arm
+    bx r0
+.arm
+.syntax divided
+.align 2
+svc_arm:
+    cmp r5, #1
+    bne fail
+    cmp r1, #7
+    bne fail
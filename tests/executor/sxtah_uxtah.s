@@ -0,0 +1,33 @@
+@ sxtah: 旋转16位后取低半字0xfffe(-2), 1 + (-2)回绕为0xffffffff
+mov r0, #1
+mov r1, #0xff000000
+orr r1, #0x00fe0000      @ r1 = 0xfffe0000
+sxtah r2, r0, r1, ror #16
+cmp r2, #0xffffffff
+bne fail
+@ sxtah: 负数半字加上0xffffffff后回绕
+mov r0, #0xffffffff
+mov r1, #0x8000          @ -32768
+sxtah r2, r0, r1
+mov r3, #0xff000000
+orr r3, #0x00ff0000
+orr r3, #0x7f00
+orr r3, #0xff            @ 0xffff7fff
+cmp r2, r3
+bne fail
+@ uxtah: 大的正半字0xffff按无符号扩展
+mov r0, #1
+mov r1, #0xff000000
+orr r1, #0x00ff0000      @ r1 = 0xffff0000
+uxtah r2, r0, r1, ror #16
+cmp r2, #0x10000
+bne fail
+@ uxtah: 加法回绕
+mov r0, #0xffffffff
+mov r1, #0xff00
+orr r1, #0xff
+uxtah r2, r0, r1
+mov r3, #0xff00
+orr r3, #0xfe            @ 0xfffe
+cmp r2, r3
+bne fail
@@ -0,0 +1,54 @@
+@ TEQ/TST(立即数)的C来自修饰立即数展开时的carry
+@ Arm: 循环移位量不为0时C是展开结果的第31位, 为0时C不变
+mov r0, #0
+msr apsr_nzcvq, #0
+teq r0, #0x80000000
+bcc fail
+bpl fail                @ 0 ^ 0x80000000是负数
+msr apsr_nzcvq, #0
+tst r0, #0xf000000f     @ 0xff循环右移4位
+bcc fail
+bne fail
+msr apsr_nzcvq, #0x20000000
+teq r0, #0x40000000
+bcs fail
+msr apsr_nzcvq, #0x20000000
+teq r0, #0xff           @ 没有循环移位, C不变
+bcc fail
+msr apsr_nzcvq, #0
+teq r0, #0xff
+bcs fail
+@ Thumb: 循环移位的形式C是第31位, 重复字节的形式C不变
+mov r4, #0
+adr r1, teq_tst_imm_thumb + 1
+bx r1
+.syntax unified
+.thumb
+.align 2
+teq_tst_imm_thumb:
+    mov r3, #0
+    msr apsr_nzcvq, r3
+    teq.w r0, #0x80000000
+    it cc
+    movcc r4, #1
+    msr apsr_nzcvq, r3
+    tst.w r0, #0xff000000
+    it cc
+    movcc r4, #2
+    mov r3, #0x20000000
+    msr apsr_nzcvq, r3
+    tst.w r0, #0x0ff00000
+    it cs
+    movcs r4, #3
+    msr apsr_nzcvq, r3
+    teq.w r0, #0x00ff00ff
+    it cc
+    movcc r4, #4
+    adr r1, teq_tst_imm_arm
+    bx r1
+.arm
+.syntax divided
+.align 2
+teq_tst_imm_arm:
+    cmp r4, #0
+    bne fail
@@ -0,0 +1,62 @@
+@ TST/TEQ的C来自移位器
+@ tst Rn, Rm, lsl Rs: 移出的最后一位是Rm的第31位
+mov r0, #0xff
+mov r1, #0x80000001
+mov r2, #1
+msr apsr_nzcvq, #0
+tst r0, r1, lsl r2
+bcc fail
+beq fail                @ 0xff & 2 != 0
+@ 移位量在Rs的低8位, 0x101当作1
+mov r2, #0x100
+orr r2, #1
+msr apsr_nzcvq, #0
+tst r0, r1, lsl r2
+bcc fail
+@ 移位量为0时C不变
+mov r2, #0
+msr apsr_nzcvq, #0x20000000
+tst r0, r1, lsl r2
+bcc fail
+msr apsr_nzcvq, #0
+tst r0, r1, lsl r2
+bcs fail
+@ lsl 32: C是Rm的第0位, 结果为0
+mov r2, #32
+tst r0, r1, lsl r2
+bcc fail
+bne fail
+@ lsl 33: C为0
+mov r2, #33
+msr apsr_nzcvq, #0x20000000
+tst r0, r1, lsl r2
+bcs fail
+@ teq Rn, Rm, lsr Rs: 移出的最后一位是Rm的第Rs-1位
+mov r1, #0x4
+mov r2, #3
+msr apsr_nzcvq, #0
+teq r0, r1, lsr r2
+bcc fail
+mov r2, #2
+msr apsr_nzcvq, #0x20000000
+teq r0, r1, lsr r2
+bcs fail
+@ teq Rn, Rm, asr Rs: 负数右移40位, C为1
+mov r1, #0x80000000
+mov r2, #40
+msr apsr_nzcvq, #0
+teq r0, r1, asr r2
+bcc fail
+bpl fail                @ 0xff ^ 0xffffffff为负
+@ 立即数: 有rotation时C是立即数的第31位
+msr apsr_nzcvq, #0
+tst r1, #0x80000000
+bcc fail
+msr apsr_nzcvq, #0x20000000
+teq r1, #0x40000000
+bcs fail
+@ 没有rotation时C不变
+msr apsr_nzcvq, #0x20000000
+tst r0, #0xff
+bcc fail
+msr apsr_nzcvq, #0
@@ -0,0 +1,32 @@
+@ umaal: 所有输入都取0xffffffff, 结果正好是0xffffffff_ffffffff
+mov r0, #0xf0000000
+msr apsr_nzcvq, r0      @ 先把N,Z,C,V都置1
+mvn r0, #0              @ RdLo
+mvn r1, #0              @ RdHi
+mvn r2, #0
+mvn r3, #0
+umaal r0, r1, r2, r3
+mrs r4, apsr
+and r4, #0xf0000000
+cmp r4, #0xf0000000     @ 标志位不变
+bne fail
+cmn r0, #1
+bne fail
+cmn r1, #1
+bne fail
+@ umaal: 0xffffffff*2+0xffffffff+1 = 0x2_fffffffe
+mov r0, #0
+msr apsr_nzcvq, r0      @ 清空N,Z,C,V
+mvn r0, #0              @ RdLo
+mov r1, #1              @ RdHi
+mvn r2, #0
+mov r3, #2
+umaal r0, r1, r2, r3
+mrs r4, apsr
+and r4, #0xf0000000
+cmp r4, #0              @ 标志位不变
+bne fail
+cmn r0, #2              @ RdLo = 0xfffffffe
+bne fail
+cmp r1, #2              @ RdHi = 2
+bne fail
@@ -0,0 +1,17 @@
+@ WFI/WFE默认当作NOP, 典型的空闲循环不会卡住
+mov r0, #0
+mov r1, #3
+wfi_loop:
+wfi
+add r0, #1
+subs r1, #1
+bne wfi_loop
+cmp r0, #3
+bne fail
+@ 没有事件时WFE也直接继续
+wfe
+wfe
+sev
+wfe
+cmp r0, #3
+bne fail
@@ -0,0 +1,42 @@
+@ 结果为0时Z置位, beq跳转
+mov r0, #5
+subs r0, r0, r0
+beq z_subs_taken
+b fail
+z_subs_taken:
+mov r0, #1
+subs r0, r0, #0
+beq fail
+ands r1, r0, #2
+bne fail
+@ 64位乘法看整个64位结果
+mov r0, #0x10000
+mov r1, #0x10000
+umulls r2, r3, r0, r1   @ 低32位为0, 高32位为1
+beq fail
+bmi fail
+mov r1, #0
+umulls r2, r3, r0, r1
+bne fail
+smulls r2, r3, r0, r1
+bne fail
+mvn r1, #0
+smulls r2, r3, r0, r1   @ -0x10000
+beq fail
+bpl fail
+@ 累加后为0
+mov r2, #0x10000
+mvn r2, r2
+add r2, r2, #1          @ -0x10000的低32位
+mvn r3, #0
+mov r1, #1
+smlals r2, r3, r0, r1
+bne fail
+cmp r2, #0
+bne fail
+cmp r3, #0
+bne fail
+mov r2, #0
+mov r3, #0
+umlals r2, r3, r0, r1
+beq fail
@@ -4,3 +4,23 @@ from enum import Enum
 class Command(Enum):
     ReadMemory = 1
     WriteMemory = 2
+    Fill = 3
+    Compare = 4
+    SetBreakpoint = 5
+    ClearBreakpoint = 6
+    BreakpointHit = 7
+    QueryMemoryMap = 8
+    ReadBlock = 9
+    WriteBlock = 10
+    ReadRegisters = 11
+    WriteRegister = 12
+    Step = 13
+    Continue = 14
+    Halt = 15
+    ReadCoverage = 16
+    ReadChar = 17
+    Reset = 18
+    AddSymbol = 19
+    ClearSymbols = 20
+    WriteChar = 21
+    ReadMemoryN = 22
@@ -3,7 +3,9 @@
 电脑需要等待单片机发送0xa5之后才能再发下一个数据
 """
 
+import os
 import struct
+import sys
 import time
 
 from command import *
@@ -15,6 +17,21 @@ from state import *
 ESCAPE_CHAR = ord("\\")
 FRAME_END = 0xFF
 
+
+def read_char():
+    """有输入时读一个字节, 没有输入时立即返回空, 不能阻塞单片机"""
+    if os.name == "nt":
+        import msvcrt
+
+        return msvcrt.getch() if msvcrt.kbhit() else b""
+    import select
+
+    if select.select([sys.stdin], [], [], 0)[0]:
+        # 不经过sys.stdin的缓冲, 否则select看不到已经读进缓冲的字节
+        return os.read(sys.stdin.fileno(), 1)
+    return b""
+
+
 port_name = None
 while port_name == None:
     print("Automatically find the correct port...", end="")
@@ -70,7 +87,30 @@ while True:
                     print("Address:", address)
                     print("Value:", value)
                     memory.write(address, value)
-            if not data:
+                case Command.ReadBlock | Command.ReadMemoryN:
+                    address, length = struct.unpack("<II", bytes(received_data))
+                    print("Address:", address)
+                    print("Length:", length)
+                    for i in range(length):
+                        data.append(memory.read(address + i))
+                case Command.WriteBlock:
+                    (address,) = struct.unpack("<I", bytes(received_data[:4]))
+                    print("Address:", address)
+                    print("Length:", len(received_data) - 4)
+                    for i, value in enumerate(received_data[4:]):
+                        memory.write(address + i, value)
+                case Command.BreakpointHit:
+                    (address,) = struct.unpack("<I", bytes(received_data))
+                    print("Breakpoint hit:", hex(address))
+                case Command.ReadChar:
+                    # 没有输入时回复空帧
+                    char = read_char()
+                    print("Char:", char)
+                    data.extend(char)
+                case Command.WriteChar:
+                    # 不需要回复
+                    print("Output:", bytes(received_data))
+            if not data and command != Command.ReadChar:
                 state = Ready()
                 continue
             i = 0